@@ -0,0 +1,186 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use jusix::{SourceSyntax, TransformOptions, TransformOutput};
+use sha2::{Digest, Sha256};
+
+/// Default cache directory, relative to the working directory
+pub const DEFAULT_CACHE_DIR: &str = ".jusix-cache";
+
+/// On-disk cache for transform outputs.
+///
+/// Entries are keyed by the hash of the source, the transform options and the
+/// jusix version, so unchanged modules are not transformed again between runs.
+pub struct Cache {
+    dir: PathBuf,
+    options_hash: String,
+    // outputs of other jusix versions are never reused
+    version: &'static str,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, options: &TransformOptions) -> Self {
+        Cache {
+            dir,
+            options_hash: hash_options(options),
+            version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+
+    /// Cache key for a module. `dependencies` identifies the state of other files that
+    /// influence the output (the reactivity manifests of imported modules), which are
+    /// located by the `module` path.
+    pub fn key(&self, filename: &str, module: &str, source: &str, dependencies: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.version);
+        hasher.update([0]);
+        hasher.update(&self.options_hash);
+        hasher.update([0]);
+        // the filename ends up in source maps and diagnostics
+        hasher.update(filename);
+        hasher.update([0]);
+        hasher.update(module);
+        hasher.update([0]);
+        hasher.update(source);
+        hasher.update([0]);
+        hasher.update(dependencies);
+        to_hex(&hasher.finalize())
+    }
+
+    pub fn get(&self, key: &str) -> Option<TransformOutput> {
+        let code = fs::read_to_string(self.entry_path(key, "js")).ok()?;
+        let map = fs::read_to_string(self.entry_path(key, "js.map")).ok();
+        let manifest = fs::read_to_string(self.entry_path(key, "reactivity.json")).ok();
+        Some(TransformOutput {
+            code,
+            map,
+            manifest,
+            warnings: vec![],
+        })
+    }
+
+    pub fn set(&self, key: &str, output: &TransformOutput) -> io::Result<()> {
+        let path = self.entry_path(key, "js");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if let Some(map) = &output.map {
+            fs::write(self.entry_path(key, "js.map"), map)?;
+        }
+        if let Some(manifest) = &output.manifest {
+            fs::write(self.entry_path(key, "reactivity.json"), manifest)?;
+        }
+        // the code is written last, an entry only counts as present if the code exists
+        fs::write(path, &output.code)
+    }
+
+    fn entry_path(&self, key: &str, extension: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(format!("{}.{}", key, extension))
+    }
+}
+
+/// Hashes the current content of the given files, missing files are skipped
+pub fn hash_files<'a>(paths: impl IntoIterator<Item = &'a Path>) -> String {
+    let mut hasher = Sha256::new();
+    for path in paths {
+        if let Ok(content) = fs::read(path) {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(content);
+            hasher.update([0]);
+        }
+    }
+    to_hex(&hasher.finalize())
+}
+
+/// Hashes all transform options that influence the output
+fn hash_options(options: &TransformOptions) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_string(&options.config).expect("config is serializable"));
+    hasher.update([0]);
+    hasher.update(match options.syntax {
+        None => "auto",
+        Some(SourceSyntax::Ts) => "ts",
+        Some(SourceSyntax::Tsx) => "tsx",
+        Some(SourceSyntax::Jsx) => "jsx",
+    });
+    hasher.update([0]);
+    hasher.update([options.source_map as u8, options.manifest as u8]);
+    hasher.update(options.env_name.as_deref().unwrap_or_default());
+    to_hex(&hasher.finalize())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Returns true if `path` contains exactly `content`, used to avoid rewriting
+/// unchanged outputs (which would trigger file watchers of other tools)
+pub fn is_up_to_date(path: &Path, content: &str) -> bool {
+    fs::read_to_string(path).is_ok_and(|existing| existing == content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jusix::config::JusixConfig;
+
+    fn cache(options: &TransformOptions) -> Cache {
+        Cache::new(PathBuf::from(DEFAULT_CACHE_DIR), options)
+    }
+
+    #[test]
+    fn key_changes_with_source() {
+        let cache = cache(&Default::default());
+        assert_eq!(cache.key("a.ts", "a.ts", "$(1)", ""), cache.key("a.ts", "a.ts", "$(1)", ""));
+        assert_ne!(cache.key("a.ts", "a.ts", "$(1)", ""), cache.key("a.ts", "a.ts", "$(2)", ""));
+        assert_ne!(cache.key("a.ts", "a.ts", "$(1)", ""), cache.key("b.ts", "b.ts", "$(1)", ""));
+    }
+
+    #[test]
+    fn key_changes_with_config() {
+        let default = cache(&Default::default());
+        let precise = cache(&TransformOptions {
+            config: JusixConfig::from_json(r#"{ "precise": true }"#).unwrap(),
+            ..Default::default()
+        });
+        let source_map = cache(&TransformOptions {
+            source_map: true,
+            ..Default::default()
+        });
+        let key = default.key("a.ts", "a.ts", "$(1)", "");
+        assert_ne!(key, precise.key("a.ts", "a.ts", "$(1)", ""));
+        assert_ne!(key, source_map.key("a.ts", "a.ts", "$(1)", ""));
+    }
+
+    #[test]
+    fn key_changes_with_version() {
+        let current = cache(&Default::default());
+        let other = Cache {
+            version: "0.0.0",
+            ..cache(&Default::default())
+        };
+        assert_ne!(current.key("a.ts", "a.ts", "$(1)", ""), other.key("a.ts", "a.ts", "$(1)", ""));
+    }
+
+    #[test]
+    fn key_changes_with_manifests() {
+        let dir = std::env::temp_dir().join(format!("jusix-cache-manifests-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("store.ts.reactivity.json");
+        let missing = dir.join("missing.ts.reactivity.json");
+        let cache = cache(&Default::default());
+
+        fs::write(&manifest, r#"{"exports":{"count":true}}"#).unwrap();
+        let before = hash_files([manifest.as_path(), missing.as_path()]);
+        assert_eq!(before, hash_files([manifest.as_path(), missing.as_path()]));
+
+        fs::write(&manifest, r#"{"exports":{"count":false}}"#).unwrap();
+        let after = hash_files([manifest.as_path(), missing.as_path()]);
+        assert_ne!(cache.key("a.ts", "a.ts", "$(1)", &before), cache.key("a.ts", "a.ts", "$(1)", &after));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
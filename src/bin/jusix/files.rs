@@ -0,0 +1,150 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Extensions of files that are picked up when transforming a directory
+const SOURCE_EXTENSIONS: [&str; 6] = ["ts", "tsx", "mts", "js", "jsx", "mjs"];
+
+/// Infix for output files that are written next to their input file
+const OUTPUT_INFIX: &str = "jusix";
+
+/// A source file and the path its transformed output is written to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// Path of the module inside the output directory (the input path if there is none),
+    /// its reactivity manifest is located by this path
+    pub module: PathBuf,
+}
+
+/// Collects all source files from the given files and directories
+pub fn collect(inputs: &[PathBuf], out_dir: Option<&Path>) -> io::Result<Vec<SourceFile>> {
+    let mut files = vec![];
+    for input in inputs {
+        if input.is_dir() {
+            let mut found = vec![];
+            walk(input, out_dir, &mut found)?;
+            found.sort();
+            for path in found {
+                let relative = path.strip_prefix(input).unwrap_or(&path).to_path_buf();
+                files.push(source_file(path, &relative, out_dir));
+            }
+        } else if input.is_file() {
+            let relative = PathBuf::from(input.file_name().unwrap_or(input.as_os_str()));
+            files.push(source_file(input.clone(), &relative, out_dir));
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist", input.display()),
+            ));
+        }
+    }
+    Ok(files)
+}
+
+/// Returns true if the path is a source file that should be transformed
+pub fn is_source_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+
+    SOURCE_EXTENSIONS.contains(&extension)
+        && !name.ends_with(".d.ts")
+        // skip our own output files
+        && !name.contains(&format!(".{}.", OUTPUT_INFIX))
+}
+
+/// Returns true for directories that are never searched for source files
+pub fn is_ignored_dir(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    name == "node_modules" || (name.starts_with('.') && name.len() > 1)
+}
+
+/// Resolves a changed path reported by the file watcher to the source file it belongs to
+pub fn resolve(path: &Path, inputs: &[PathBuf], out_dir: Option<&Path>) -> Option<SourceFile> {
+    if !path.is_file() || out_dir.is_some_and(|out_dir| is_inside(path, out_dir)) {
+        return None;
+    }
+    for input in inputs {
+        // inputs can be removed while watching, the other inputs are still resolved
+        let Ok(root) = input.canonicalize() else {
+            continue;
+        };
+        if input.is_file() {
+            if root == path {
+                let relative = PathBuf::from(input.file_name().unwrap_or(input.as_os_str()));
+                return Some(source_file(input.clone(), &relative, out_dir));
+            }
+        } else if let Ok(relative) = path.strip_prefix(&root) {
+            let ignored = relative.ancestors().skip(1).any(is_ignored_dir);
+            if is_source_file(path) && !ignored {
+                return Some(source_file(input.join(relative), relative, out_dir));
+            }
+        }
+    }
+    None
+}
+
+fn is_inside(path: &Path, dir: &Path) -> bool {
+    match (path.canonicalize(), dir.canonicalize()) {
+        (Ok(path), Ok(dir)) => path.starts_with(dir),
+        _ => false,
+    }
+}
+
+fn walk(dir: &Path, out_dir: Option<&Path>, found: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            // don't pick up our own output if the output directory is inside an input directory
+            let is_out_dir = out_dir.is_some_and(|out_dir| is_inside(&path, out_dir));
+            if !is_ignored_dir(&path) && !is_out_dir {
+                walk(&path, out_dir, found)?;
+            }
+        } else if is_source_file(&path) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Output path for a source file, either inside `out_dir` (keeping the path relative to
+/// the input directory) or next to the input as `<name>.jusix.<ext>`
+pub fn source_file(input: PathBuf, relative: &Path, out_dir: Option<&Path>) -> SourceFile {
+    let module = match out_dir {
+        Some(_) => relative.to_path_buf(),
+        None => input.clone(),
+    };
+    let output = match out_dir {
+        Some(out_dir) => out_dir.join(relative),
+        None => {
+            let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+            let name = match input.extension().and_then(|e| e.to_str()) {
+                Some(extension) => format!("{}.{}.{}", stem, OUTPUT_INFIX, extension),
+                None => format!("{}.{}", stem, OUTPUT_INFIX),
+            };
+            input.with_file_name(name)
+        }
+    };
+    SourceFile { input, output, module }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_skips_missing_inputs() {
+        let dir = std::env::temp_dir().join(format!("jusix-resolve-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let path = dir.join("src").join("app.tsx");
+        fs::write(&path, "").unwrap();
+
+        let inputs = [dir.join("removed"), dir.join("src")];
+        let resolved = resolve(&path.canonicalize().unwrap(), &inputs, None).unwrap();
+        assert_eq!(resolved.input, dir.join("src").join("app.tsx"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
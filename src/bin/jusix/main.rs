@@ -0,0 +1,379 @@
+//! `jusix` command-line tool.
+//!
+//! Applies the jusix reactivity transforms to files or directories and writes the
+//! result next to the inputs (`<name>.jusix.<ext>`) or into an output directory.
+//! Useful to inspect what the transform does without a full UIX build.
+//! In `--watch` mode, only changed files are transformed again, and unchanged
+//! files are skipped between runs via an on-disk cache.
+
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, ValueEnum};
+use jusix::{config::JusixConfig, manifest, transform, SourceSyntax, TransformOptions};
+
+mod cache;
+mod files;
+mod watch;
+
+use cache::Cache;
+use files::SourceFile;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SyntaxArg {
+    Ts,
+    Tsx,
+    Jsx,
+}
+
+impl From<SyntaxArg> for SourceSyntax {
+    fn from(syntax: SyntaxArg) -> Self {
+        match syntax {
+            SyntaxArg::Ts => SourceSyntax::Ts,
+            SyntaxArg::Tsx => SourceSyntax::Tsx,
+            SyntaxArg::Jsx => SourceSyntax::Jsx,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "jusix", version, about = "Applies the JUSIX reactivity transforms to JS/TS files")]
+struct Args {
+    /// Files or directories to transform
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Write output files into this directory instead of next to the inputs
+    #[arg(short, long)]
+    out_dir: Option<PathBuf>,
+
+    /// Source syntax, inferred from the file extension if not set
+    #[arg(long, value_enum)]
+    syntax: Option<SyntaxArg>,
+
+    /// Write a source map next to each output file
+    #[arg(long)]
+    source_maps: bool,
+
+    /// Print the transformed code to stdout instead of writing files
+    #[arg(long, conflicts_with_all = ["out_dir", "source_maps"])]
+    stdout: bool,
+
+    /// Plugin config, either inline JSON or a path to a JSON file.
+    /// Accepts the same options as the SWC plugin config.
+    #[arg(short, long)]
+    config: Option<String>,
+
+    /// Environment name passed to the transform (e.g. `development`)
+    #[arg(long)]
+    env_name: Option<String>,
+
+    /// Keep running and transform files again when they change
+    #[arg(short, long, conflicts_with = "stdout")]
+    watch: bool,
+
+    /// Directory for cached transform outputs
+    #[arg(long, default_value = cache::DEFAULT_CACHE_DIR)]
+    cache_dir: PathBuf,
+
+    /// Always transform all files, without reading or writing the cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Write a reactivity manifest for each module into the manifest directory
+    /// (`manifestDir` config, defaults to the output directory) and use the manifests
+    /// of imported modules in precise mode
+    #[arg(long, conflicts_with = "stdout")]
+    manifests: bool,
+}
+
+impl Args {
+    fn transform_options(&self) -> Result<TransformOptions, String> {
+        let mut config = match &self.config {
+            None => JusixConfig::default(),
+            Some(config) => {
+                let json = if config.trim_start().starts_with('{') {
+                    config.clone()
+                } else {
+                    fs::read_to_string(config)
+                        .map_err(|e| format!("Could not read config file {}: {}", config, e))?
+                };
+                JusixConfig::from_json(&json).map_err(|e| format!("Invalid config: {}", e))?
+            }
+        };
+
+        if self.manifests && config.manifest_dir.is_none() {
+            let dir = self.out_dir.as_deref().unwrap_or(Path::new("."));
+            config.manifest_dir = Some(dir.to_string_lossy().into_owned());
+        }
+
+        Ok(TransformOptions {
+            syntax: self.syntax.map(Into::into),
+            source_map: self.source_maps,
+            env_name: self.env_name.clone(),
+            manifest: self.manifests,
+            module_path: None,
+            config,
+        })
+    }
+}
+
+/// What happened to a file in [`Transformer::transform_file`]
+enum Outcome {
+    Transformed,
+    Cached,
+}
+
+struct Transformer {
+    options: TransformOptions,
+    cache: Option<Cache>,
+    stdout: bool,
+    /// All source files, transformed again when a reactivity manifest changes
+    files: Vec<SourceFile>,
+    /// Set when a manifest was written in the current pass
+    manifests_changed: Cell<bool>,
+}
+
+impl Transformer {
+    /// Directory the reactivity manifests are written to, if enabled
+    fn manifest_dir(&self) -> Option<&Path> {
+        self.options
+            .manifest
+            .then_some(self.options.config.manifest_dir.as_deref())
+            .flatten()
+            .map(Path::new)
+    }
+
+    /// Identifies the current state of all manifests, which the outputs depend on
+    fn manifests_hash(&self) -> String {
+        let Some(dir) = self.manifest_dir() else {
+            return String::new();
+        };
+        let paths: Vec<PathBuf> = self
+            .files
+            .iter()
+            .map(|file| manifest::manifest_path(dir, &file.module))
+            .collect();
+        cache::hash_files(paths.iter().map(PathBuf::as_path))
+    }
+
+    /// Transforms a single file and writes the output, returns an error message on failure
+    fn transform_file(&self, file: &SourceFile, dependencies: &str) -> Result<Outcome, String> {
+        let source = fs::read_to_string(&file.input)
+            .map_err(|e| format!("Could not read {}: {}", file.input.display(), e))?;
+        let filename = file.input.to_string_lossy();
+        let module = file.module.to_string_lossy();
+
+        let key = self
+            .cache
+            .as_ref()
+            .map(|cache| cache.key(&filename, &module, &source, dependencies));
+        let cached = self
+            .cache
+            .as_ref()
+            .zip(key.as_deref())
+            .and_then(|(cache, key)| cache.get(key));
+
+        let (output, outcome) = match cached {
+            Some(output) => (output, Outcome::Cached),
+            None => {
+                // manifests mirror the output directory, so that they are next to the outputs
+                let options = TransformOptions {
+                    module_path: Some(module.to_string()),
+                    ..self.options.clone()
+                };
+                let output = transform(&source, &filename, &options)
+                    .map_err(|diagnostics| diagnostics.to_string())?;
+                for warning in &output.warnings {
+                    eprintln!("warning: {}", warning);
+                }
+                // outputs with warnings are not cached, so the warnings are shown on every run
+                if let (Some(cache), Some(key), true) = (&self.cache, &key, output.warnings.is_empty()) {
+                    // a broken cache only costs performance, so errors are just reported
+                    if let Err(e) = cache.set(key, &output) {
+                        eprintln!("warning: Could not write cache entry: {}", e);
+                    }
+                }
+                (output, Outcome::Transformed)
+            }
+        };
+
+        if self.stdout {
+            print!("{}", output.code);
+            return Ok(outcome);
+        }
+
+        if let Some(parent) = file.output.parent() {
+            create_dir(parent)?;
+        }
+
+        if let (Some(dir), Some(manifest)) = (self.manifest_dir(), &output.manifest) {
+            let path = manifest::manifest_path(dir, &file.module);
+            if !cache::is_up_to_date(&path, manifest) {
+                if let Some(parent) = path.parent() {
+                    create_dir(parent)?;
+                }
+                fs::write(&path, manifest)
+                    .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+                self.manifests_changed.set(true);
+            }
+        }
+
+        let mut code = output.code;
+        if let Some(map) = output.map {
+            let map_path = PathBuf::from(format!("{}.map", file.output.display()));
+            write_if_changed(&map_path, &map)?;
+            let map_name = map_path.file_name().unwrap_or_default().to_string_lossy();
+            code.push_str(&format!("//# sourceMappingURL={}\n", map_name));
+        }
+        write_if_changed(&file.output, &code)?;
+        Ok(outcome)
+    }
+
+    /// Transforms all files, returns the number of failed files.
+    /// If a reactivity manifest changed, all files are transformed again, because
+    /// modules that import the changed module can have a different output now.
+    fn transform_files(&self, files: &[SourceFile]) -> usize {
+        let mut results = BTreeMap::new();
+        let mut pass = files;
+        for _ in 0..=self.files.len() {
+            self.manifests_changed.set(false);
+            let dependencies = self.manifests_hash();
+            for file in pass {
+                results.insert(&file.input, self.transform_file(file, &dependencies));
+            }
+            if !self.manifests_changed.get() {
+                break;
+            }
+            pass = &self.files;
+        }
+
+        let mut failed = 0;
+        let mut cached = 0;
+        for result in results.values() {
+            match result {
+                Ok(Outcome::Cached) => cached += 1,
+                Ok(Outcome::Transformed) => {}
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            eprintln!("{} of {} files failed", failed, results.len());
+        } else if !self.stdout {
+            eprintln!(
+                "Transformed {} files ({} unchanged)",
+                results.len() - cached,
+                cached
+            );
+        }
+        failed
+    }
+}
+
+fn create_dir(path: &Path) -> Result<(), String> {
+    fs::create_dir_all(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))
+}
+
+fn write_if_changed(path: &Path, content: &str) -> Result<(), String> {
+    if cache::is_up_to_date(path, content) {
+        return Ok(());
+    }
+    fs::write(path, content).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+fn main() -> ExitCode {
+    run(Args::parse())
+}
+
+fn run(args: Args) -> ExitCode {
+    let options = match args.transform_options() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let files = match files::collect(&args.inputs, args.out_dir.as_deref()) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let transformer = Transformer {
+        cache: (!args.no_cache).then(|| Cache::new(args.cache_dir.clone(), &options)),
+        options,
+        stdout: args.stdout,
+        files: files.clone(),
+        manifests_changed: Cell::new(false),
+    };
+    let failed = transformer.transform_files(&files);
+
+    if args.watch {
+        eprintln!("Watching for changes...");
+        let result = watch::watch(&args.inputs, args.out_dir.as_deref(), |changed| {
+            transformer.transform_files(changed);
+        });
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+        }
+        return ExitCode::FAILURE;
+    }
+
+    if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifests_next_to_outputs() {
+        let dir = std::env::temp_dir().join(format!("jusix-cli-manifests-{}", std::process::id()));
+        let src = dir.join("src");
+        let out = dir.join("out");
+        fs::create_dir_all(src.join("views")).unwrap();
+        fs::write(src.join("store.ts"), r#"export const count = $(0); export const LABEL = "Total";"#).unwrap();
+        fs::write(
+            src.join("views").join("app.tsx"),
+            r#"import { count, LABEL } from "../store.ts"; <div>{count + 1}{LABEL + "!"}</div>;"#,
+        )
+        .unwrap();
+
+        let args = Args::parse_from([
+            "jusix".as_ref(),
+            src.as_os_str(),
+            "-o".as_ref(),
+            out.as_os_str(),
+            "--manifests".as_ref(),
+            "--no-cache".as_ref(),
+            "-c".as_ref(),
+            r#"{"precise": true}"#.as_ref(),
+        ]);
+        let code = run(args);
+
+        let manifest = fs::read_to_string(out.join("store.ts.reactivity.json"));
+        let app = fs::read_to_string(out.join("views").join("app.tsx"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(code, ExitCode::SUCCESS);
+        let manifest = manifest::ReactivityManifest::from_json(&manifest.unwrap()).unwrap();
+        assert_eq!(manifest.exports.get("LABEL"), Some(&false));
+        // the manifest of store.ts is found from the output of app.tsx
+        assert!(app.unwrap().contains(r#"{_$(()=>count + 1)}{LABEL + "!"}"#));
+    }
+}
@@ -0,0 +1,55 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{Event, RecursiveMode, Watcher};
+
+use crate::files::{self, SourceFile};
+
+/// Time to wait for more events after a change, editors often write files in several steps
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Watches the inputs and calls `on_change` with all source files that were created
+/// or modified. Blocks until the watcher fails.
+pub fn watch(
+    inputs: &[PathBuf],
+    out_dir: Option<&Path>,
+    mut on_change: impl FnMut(&[SourceFile]),
+) -> notify::Result<()> {
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for input in inputs {
+        let mode = if input.is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(input, mode)?;
+    }
+
+    while let Ok(event) = rx.recv() {
+        let mut paths = BTreeSet::new();
+        collect_paths(event?, &mut paths);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_paths(event?, &mut paths);
+        }
+
+        let changed: Vec<SourceFile> = paths
+            .iter()
+            .filter_map(|path| files::resolve(path, inputs, out_dir))
+            .collect();
+        if !changed.is_empty() {
+            on_change(&changed);
+        }
+    }
+    Ok(())
+}
+
+fn collect_paths(event: Event, paths: &mut BTreeSet<PathBuf>) {
+    if event.kind.is_create() || event.kind.is_modify() {
+        paths.extend(event.paths);
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Options accepted by the jusix plugin.
+///
+/// Passed as the second element of the plugin entry in `.swcrc`:
+/// `["./jusix.wasm", { "frontendNamespace": "frontend" }]`.
+/// Unknown keys are rejected so that typos don't silently fall back to defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+pub struct JusixConfig {
+    /// JSX attribute namespace that marks transferable closures,
+    /// e.g. `onclick:frontend={() => ...}`
+    pub frontend_namespace: String,
+    /// Names of the runtime helpers that are emitted and recognized
+    pub helpers: HelperNames,
+    /// Module specifier to import the emitted helpers (`_$`, `$$`, `prop`, `_$if`, `_$tpl`, `_$iter`) from.
+    /// If not set, the helpers are expected to be globals.
+    /// `use` is never imported, because it runs inside transferable closures.
+    pub import_source: Option<String>,
+    /// Global variables that are never captured with `use()` in transferable closures.
+    /// Replaces the default list of well-known JavaScript and browser globals.
+    pub globals: Vec<String>,
+    /// Modules that export the UIX/DATEX helpers (`$`, `run`, `_$`, `$$`).
+    /// Calls are only transformed if the callee is imported from one of these modules
+    /// (or a subpath, e.g. `datex-core-legacy/datex.ts`) or is an unshadowed global.
+    /// The `import_source` is always included.
+    pub helper_modules: Vec<String>,
+    /// Only wrap expressions that reference a reactive binding, i.e. a variable that is
+    /// initialized with `$()`, `$$()`, `_$()` or one of the `reactive_functions`, or an import
+    /// from another module.
+    /// Expressions that only read other variables (e.g. function parameters) are not wrapped.
+    pub precise: bool,
+    /// Directory with the reactivity manifests of other modules, used in precise mode to
+    /// find out which imported bindings are reactive. Imports without a manifest are
+    /// treated as reactive. See [`crate::manifest`].
+    pub manifest_dir: Option<String>,
+    /// Collection methods and properties that are converted to their reactive `$` variant,
+    /// e.g. `items.map(fn)` to `items.$.map(fn)`. Replaces the default table.
+    pub collection_methods: BTreeMap<String, CollectionMethod>,
+    /// JSX attributes whose values are passed as they are, in addition to event
+    /// handlers (`on*`) and `ref`, which are never wrapped
+    pub plain_attributes: Vec<String>,
+    /// Functions that return reactive values (`always(...)`, `effect(...)`, `prop(...)`).
+    /// Their calls are passed through as they are instead of being wrapped again.
+    /// Only calls of unshadowed globals or of imports from the `helper_modules` are recognized.
+    pub reactive_functions: Vec<String>,
+}
+
+impl Default for JusixConfig {
+    fn default() -> Self {
+        JusixConfig {
+            frontend_namespace: "frontend".into(),
+            helpers: HelperNames::default(),
+            import_source: None,
+            globals: DEFAULT_GLOBALS.iter().map(|g| g.to_string()).collect(),
+            helper_modules: DEFAULT_HELPER_MODULES.iter().map(|m| m.to_string()).collect(),
+            precise: false,
+            manifest_dir: None,
+            collection_methods: DEFAULT_COLLECTION_METHODS
+                .iter()
+                .map(|(name, callbacks, collection_only)| {
+                    let mut method = match callbacks {
+                        Some(callbacks) => CollectionMethod::method(callbacks),
+                        None => CollectionMethod::property(),
+                    };
+                    method.collection_only = *collection_only;
+                    (name.to_string(), method)
+                })
+                .collect(),
+            plain_attributes: DEFAULT_PLAIN_ATTRIBUTES.iter().map(|a| a.to_string()).collect(),
+            reactive_functions: DEFAULT_REACTIVE_FUNCTIONS.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+}
+
+/// Well-known globals that are available in every JavaScript context
+pub const DEFAULT_GLOBALS: &[&str] = &[
+    // language
+    "globalThis", "undefined", "NaN", "Infinity", "arguments",
+    "Object", "Function", "Array", "String", "Number", "Boolean", "Symbol", "BigInt",
+    "Date", "RegExp", "Math", "JSON", "Reflect", "Proxy", "Promise", "Intl",
+    "Map", "Set", "WeakMap", "WeakSet", "WeakRef",
+    "Error", "TypeError", "RangeError", "SyntaxError", "ReferenceError", "AggregateError",
+    "ArrayBuffer", "DataView", "Int8Array", "Uint8Array", "Uint8ClampedArray", "Int16Array",
+    "Uint16Array", "Int32Array", "Uint32Array", "Float32Array", "Float64Array",
+    "BigInt64Array", "BigUint64Array",
+    "parseInt", "parseFloat", "isNaN", "isFinite",
+    "encodeURI", "encodeURIComponent", "decodeURI", "decodeURIComponent",
+    // web platform
+    "console", "window", "self", "document", "navigator", "location", "history",
+    "localStorage", "sessionStorage", "crypto", "performance",
+    "setTimeout", "clearTimeout", "setInterval", "clearInterval", "queueMicrotask",
+    "requestAnimationFrame", "cancelAnimationFrame", "structuredClone",
+    "fetch", "Request", "Response", "Headers", "URL", "URLSearchParams", "FormData", "Blob", "File",
+    "TextEncoder", "TextDecoder", "atob", "btoa", "alert", "confirm", "prompt",
+    "Event", "CustomEvent", "EventTarget", "HTMLElement", "Element", "Node",
+];
+
+/// Modules that export the UIX/DATEX runtime helpers
+pub const DEFAULT_HELPER_MODULES: &[&str] = &["datex", "datex-core-legacy", "uix", "unyt_core"];
+
+/// UIX/DATEX functions that create reactive values, in addition to `$`, `$$` and `_$`
+pub const DEFAULT_REACTIVE_FUNCTIONS: &[&str] = &["always", "effect", "prop", "val", "ref"];
+
+/// Attributes that are read once by the runtime, so their values are never wrapped
+pub const DEFAULT_PLAIN_ATTRIBUTES: &[&str] = &["key"];
+
+/// DOM events that can be handled with lowercase attributes like `onclick`,
+/// from the GlobalEventHandlers and WindowEventHandlers of the HTML standard
+const DOM_EVENTS: &[&str] = &[
+    // mouse and pointer
+    "click", "dblclick", "contextmenu", "auxclick", "mousedown", "mouseup", "mousemove", "mouseover",
+    "mouseout", "mouseenter", "mouseleave", "wheel", "pointerdown", "pointerup", "pointermove",
+    "pointerover", "pointerout", "pointerenter", "pointerleave", "pointercancel",
+    "gotpointercapture", "lostpointercapture", "touchstart", "touchend", "touchmove", "touchcancel",
+    "drag", "dragstart", "dragend", "dragenter", "dragleave", "dragover", "drop",
+    // keyboard, forms and selection
+    "keydown", "keyup", "keypress", "input", "beforeinput", "change", "submit", "reset", "invalid",
+    "formdata", "focus", "blur", "focusin", "focusout", "select", "selectstart", "selectionchange",
+    "copy", "cut", "paste",
+    // media
+    "abort", "canplay", "canplaythrough", "durationchange", "emptied", "ended", "loadeddata",
+    "loadedmetadata", "loadstart", "pause", "play", "playing", "progress", "ratechange", "seeked",
+    "seeking", "stalled", "suspend", "timeupdate", "volumechange", "waiting", "cuechange",
+    // loading, layout and elements
+    "load", "error", "scroll", "scrollend", "resize", "toggle", "beforetoggle", "beforematch",
+    "close", "cancel", "slotchange", "contextlost", "contextrestored", "securitypolicyviolation",
+    "fullscreenchange", "fullscreenerror",
+    "animationstart", "animationend", "animationiteration", "animationcancel",
+    "transitionstart", "transitionend", "transitionrun", "transitioncancel",
+    // window
+    "message", "messageerror", "afterprint", "beforeprint", "beforeunload", "unload", "hashchange",
+    "popstate", "pagehide", "pageshow", "storage", "languagechange", "online", "offline",
+    "rejectionhandled", "unhandledrejection",
+];
+
+/// Collection methods with the indices of their callback arguments (`None` for properties),
+/// and whether they are only converted on known collections, see [`CollectionMethod::collection_only`]
+pub const DEFAULT_COLLECTION_METHODS: &[(&str, Option<&[usize]>, bool)] = &[
+    ("map", Some(&[0]), false),
+    ("filter", Some(&[0]), false),
+    ("reduce", Some(&[0]), false),
+    ("find", Some(&[0]), false),
+    ("some", Some(&[0]), false),
+    ("every", Some(&[0]), false),
+    ("flatMap", Some(&[0]), false),
+    ("sort", Some(&[0]), false),
+    // strings also have these
+    ("slice", Some(&[]), true),
+    ("join", Some(&[]), true),
+    ("includes", Some(&[]), true),
+    ("length", None, true),
+];
+
+/// How a collection method is converted to its reactive `$` variant.
+///
+/// `{ "callbacks": [0] }` for `map(fn)`, `{ "property": true }` for `length`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+pub struct CollectionMethod {
+    /// Indices of the arguments that are callbacks, their bodies are transformed like JSX children
+    pub callbacks: Vec<usize>,
+    /// Read as a property instead of called, e.g. `items.$.length`
+    pub property: bool,
+    /// Only converted if the receiver is a variable that always holds an array, Map or Set
+    /// (`const items = $([])`), for methods that other values also have, e.g. `label.slice(0, 3)`
+    pub collection_only: bool,
+}
+
+impl CollectionMethod {
+    pub fn method(callbacks: &[usize]) -> Self {
+        CollectionMethod {
+            callbacks: callbacks.to_vec(),
+            property: false,
+            collection_only: false,
+        }
+    }
+
+    pub fn property() -> Self {
+        CollectionMethod {
+            callbacks: vec![],
+            property: true,
+            collection_only: false,
+        }
+    }
+}
+
+/// Names of the UIX/DATEX runtime helpers.
+///
+/// Can be changed to avoid clashes, e.g. with a jQuery-style `$`
+/// or with a local `prop` variable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+pub struct HelperNames {
+    /// Reactive shortcut function written by the user, `$(x + 1)`
+    pub dollar: String,
+    /// Wraps a reactive expression, `_$(() => x + 1)`
+    pub always: String,
+    /// Creates a pointer from a static value, `$$(10)`
+    pub pointer: String,
+    /// Reactive property access, `prop(x, 'name')`
+    pub prop: String,
+    /// Declares the variables captured by a transferable closure, `use(x, y)`
+    #[serde(rename = "use")]
+    pub use_: String,
+    /// Runs a transferable closure, `run(() => ...)`
+    pub run: String,
+    /// Reactive conditional for JSX children, `_$if(() => cond, () => <A/>, () => <B/>)`
+    #[serde(rename = "if")]
+    pub if_: String,
+    /// Reactive template literal tag, `` _$tpl`Hello ${prop(user, 'name')}` ``
+    pub template: String,
+    /// Keyed reactive iteration over objects, maps and sets, `_$iter(form, 'entries')`
+    pub iterate: String,
+}
+
+impl Default for HelperNames {
+    fn default() -> Self {
+        HelperNames {
+            dollar: "$".into(),
+            always: "_$".into(),
+            pointer: "$$".into(),
+            prop: "prop".into(),
+            use_: "use".into(),
+            run: "run".into(),
+            if_: "_$if".into(),
+            template: "_$tpl".into(),
+            iterate: "_$iter".into(),
+        }
+    }
+}
+
+impl JusixConfig {
+    /// Parses the plugin config JSON, treating an empty string as the default config
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        if json.trim().is_empty() {
+            return Ok(JusixConfig::default());
+        }
+        serde_json::from_str(json)
+    }
+
+    /// Returns true if the value of the JSX attribute `name` is passed as it is:
+    /// event handlers (`onClick`, `onclick`), `ref` and the [`plain_attributes`](Self::plain_attributes)
+    pub fn is_plain_attribute(&self, name: &str) -> bool {
+        let is_event_handler = name.strip_prefix("on").is_some_and(|event| {
+            event.starts_with(|c: char| c.is_ascii_uppercase()) || DOM_EVENTS.contains(&event)
+        });
+        is_event_handler
+            || name == "ref"
+            || self.plain_attributes.iter().any(|a| a == name)
+    }
+
+    /// Returns true if `specifier` refers to one of the [`helper_modules`](Self::helper_modules)
+    /// or to the [`import_source`](Self::import_source)
+    pub fn is_helper_module(&self, specifier: &str) -> bool {
+        self.helper_modules
+            .iter()
+            .chain(&self.import_source)
+            .any(|module| {
+                specifier == module
+                    || specifier
+                        .strip_prefix(module.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+}
+
+/// Information about the module that is being transformed,
+/// provided by the SWC plugin host or by the embedding tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransformMetadata {
+    /// Name of the file that is being transformed
+    pub filename: Option<String>,
+    /// Name of the environment (e.g. `development` or `production`)
+    pub env_name: Option<String>,
+    /// Path of the module inside the manifest directory, defaults to the filename
+    pub module_path: Option<String>,
+}
@@ -0,0 +1,77 @@
+use swc_ecma_ast::{
+    ArrayLit, BinaryOp, Expr, Ident, Lit, MemberProp, ObjectLit, Prop, PropName, PropOrSpread, Tpl,
+    UnaryOp,
+};
+
+/// Returns true if `expr` always evaluates to the same value and has no side effects,
+/// so it does not need to be wrapped in a reactive `_$()` closure.
+///
+/// Constant expressions are built only from literals, static templates, array and object
+/// literals, property reads and operators without side effects, e.g. `1 + 2`, `` `static` ``,
+/// `[1, 2, 3]` or `-1`. The only identifiers they can contain are bindings for which
+/// `is_constant_binding` returns true, e.g. module-level `const LABEL = "Total"`.
+pub fn is_constant_expr(expr: &Expr, is_constant_binding: &dyn Fn(&Ident) -> bool) -> bool {
+    let is_constant_expr = |e: &Expr| is_constant_expr(e, is_constant_binding);
+    match expr {
+        Expr::Ident(ident) => is_constant_binding(ident),
+        Expr::Lit(lit) => !matches!(lit, Lit::JSXText(_)),
+        Expr::Paren(p) => is_constant_expr(&p.expr),
+        Expr::Tpl(tpl) => is_constant_tpl(tpl, is_constant_binding),
+        Expr::Array(array) => is_constant_array(array, is_constant_binding),
+        Expr::Object(object) => is_constant_object(object, is_constant_binding),
+        // properties of constant values, e.g. `strings.title` for an imported JSON module
+        Expr::Member(member) => {
+            let prop_is_constant = match &member.prop {
+                MemberProp::Ident(_) => true,
+                MemberProp::Computed(c) => is_constant_expr(&c.expr),
+                MemberProp::PrivateName(_) => false,
+            };
+            prop_is_constant && is_constant_expr(&member.obj)
+        }
+        Expr::Unary(unary) => unary.op != UnaryOp::Delete && is_constant_expr(&unary.arg),
+        // `in` and `instanceof` can call user code (proxies, Symbol.hasInstance)
+        Expr::Bin(bin) => {
+            !matches!(bin.op, BinaryOp::In | BinaryOp::InstanceOf)
+                && is_constant_expr(&bin.left)
+                && is_constant_expr(&bin.right)
+        }
+        Expr::Cond(cond) => {
+            is_constant_expr(&cond.test)
+                && is_constant_expr(&cond.cons)
+                && is_constant_expr(&cond.alt)
+        }
+        _ => false,
+    }
+}
+
+fn is_constant_tpl(tpl: &Tpl, is_constant_binding: &dyn Fn(&Ident) -> bool) -> bool {
+    tpl.exprs
+        .iter()
+        .all(|e| is_constant_expr(e, is_constant_binding))
+}
+
+fn is_constant_array(array: &ArrayLit, is_constant_binding: &dyn Fn(&Ident) -> bool) -> bool {
+    array
+        .elems
+        .iter()
+        .flatten()
+        .all(|elem| elem.spread.is_none() && is_constant_expr(&elem.expr, is_constant_binding))
+}
+
+fn is_constant_object(object: &ObjectLit, is_constant_binding: &dyn Fn(&Ident) -> bool) -> bool {
+    let is_constant_expr = |e: &Expr| is_constant_expr(e, is_constant_binding);
+    // methods, getters and spreads are excluded, they can run user code
+    object.props.iter().all(|prop| match prop {
+        PropOrSpread::Prop(prop) => match &**prop {
+            Prop::KeyValue(kv) => {
+                let key_is_constant = match &kv.key {
+                    PropName::Computed(c) => is_constant_expr(&c.expr),
+                    _ => true,
+                };
+                key_is_constant && is_constant_expr(&kv.value)
+            }
+            _ => false,
+        },
+        PropOrSpread::Spread(_) => false,
+    })
+}
@@ -0,0 +1,18 @@
+use swc_common::{errors::HANDLER, Span};
+
+/// Reports an error through the swc [`HANDLER`] of the current transform.
+///
+/// The node the error is reported for should be left unchanged. Does nothing if no
+/// handler is set, e.g. if the pass is used in a pipeline without error reporting.
+pub(crate) fn error(span: Span, message: &str) {
+    if HANDLER.is_set() {
+        HANDLER.with(|handler| handler.struct_span_err(span, message).emit());
+    }
+}
+
+/// Reports a warning through the swc [`HANDLER`] of the current transform, see [`error`]
+pub(crate) fn warning(span: Span, message: &str) {
+    if HANDLER.is_set() {
+        HANDLER.with(|handler| handler.struct_span_warn(span, message).emit());
+    }
+}
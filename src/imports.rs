@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+
+use swc_ecma_ast::{
+    BindingIdent, ClassDecl, ClassExpr, FnDecl, FnExpr, Ident, ImportDecl, ImportNamedSpecifier,
+    ImportPhase, ImportSpecifier, Module, ModuleDecl, ModuleExportName, ModuleItem, Script, Str,
+};
+use swc_ecma_visit::{Visit, VisitWith};
+use swc_common::DUMMY_SP;
+
+/// Injects `import { _$, prop, $$ } from "<specifier>"` for the helpers a module uses.
+///
+/// Helpers that are already imported from the specifier are reused, and helpers whose
+/// name is already declared in the module are imported under an alias (`_$1`, `prop1`, ...).
+pub(crate) struct HelperImports {
+    specifier: String,
+    /// helper name -> local name in the module
+    locals: HashMap<String, String>,
+    /// helpers that are already imported from the specifier
+    existing: HashSet<String>,
+    /// helpers that were emitted, in order of first use
+    used: Vec<String>,
+}
+
+impl HelperImports {
+    pub fn new(module: &Module, specifier: &str, helpers: &[&str]) -> Self {
+        let mut collector = DeclaredNames {
+            specifier,
+            ..Default::default()
+        };
+        module.visit_with(&mut collector);
+        Self::with_names(collector, specifier, helpers)
+    }
+
+    /// Tracks the helpers used by a script, which can't import them, see [`Self::missing`]
+    pub fn for_script(script: &Script, specifier: &str, helpers: &[&str]) -> Self {
+        let mut collector = DeclaredNames {
+            specifier,
+            ..Default::default()
+        };
+        script.visit_with(&mut collector);
+        Self::with_names(collector, specifier, helpers)
+    }
+
+    fn with_names(collector: DeclaredNames, specifier: &str, helpers: &[&str]) -> Self {
+        let mut locals = HashMap::new();
+        let mut existing = HashSet::new();
+        for helper in helpers {
+            if let Some(local) = collector.imported.get(*helper) {
+                existing.insert(helper.to_string());
+                locals.insert(helper.to_string(), local.clone());
+            } else if collector.declared.contains(*helper) {
+                let alias = (1..)
+                    .map(|i| format!("{}{}", helper, i))
+                    .find(|alias| !collector.declared.contains(alias))
+                    .expect("infinite iterator");
+                locals.insert(helper.to_string(), alias);
+            }
+        }
+
+        HelperImports {
+            specifier: specifier.to_string(),
+            locals,
+            existing,
+            used: vec![],
+        }
+    }
+
+    /// Marks a helper as used and returns its local name
+    pub fn use_helper(&mut self, helper: &str) -> String {
+        if !self.used.iter().any(|h| h == helper) {
+            self.used.push(helper.to_string());
+        }
+        self.locals
+            .get(helper)
+            .cloned()
+            .unwrap_or_else(|| helper.to_string())
+    }
+
+    /// Used helpers that are not imported yet
+    pub fn missing(&self) -> impl Iterator<Item = &String> {
+        self.used.iter().filter(|helper| !self.existing.contains(*helper))
+    }
+
+    /// Imports all used helpers that are not imported yet, either by adding them to an
+    /// existing import from the specifier or with a new import
+    pub fn inject(self, module: &mut Module) {
+        let mut specifiers: Vec<ImportSpecifier> = self
+            .missing()
+            .map(|helper| {
+                let local = self.locals.get(helper).unwrap_or(helper);
+                ImportSpecifier::Named(ImportNamedSpecifier {
+                    span: DUMMY_SP,
+                    local: Ident::new(local.as_str().into(), DUMMY_SP, Default::default()),
+                    imported: (local != helper).then(|| {
+                        ModuleExportName::Ident(Ident::new(
+                            helper.as_str().into(),
+                            DUMMY_SP,
+                            Default::default(),
+                        ))
+                    }),
+                    is_type_only: false,
+                })
+            })
+            .collect();
+
+        if specifiers.is_empty() {
+            return;
+        }
+
+        // `import * as ns from "..."` can't be combined with named imports
+        let existing = module.body.iter_mut().find_map(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import))
+                if import.src.value == *self.specifier
+                    && !import.type_only
+                    && import.phase == ImportPhase::Evaluation
+                    && !import.specifiers.iter().any(ImportSpecifier::is_namespace) =>
+            {
+                Some(import)
+            }
+            _ => None,
+        });
+        if let Some(import) = existing {
+            import.specifiers.append(&mut specifiers);
+            return;
+        }
+
+        module.body.insert(
+            0,
+            ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                span: DUMMY_SP,
+                specifiers,
+                src: Box::new(Str {
+                    span: DUMMY_SP,
+                    value: self.specifier.as_str().into(),
+                    raw: None,
+                }),
+                type_only: false,
+                with: None,
+                phase: ImportPhase::Evaluation,
+            })),
+        );
+    }
+}
+
+/// Collects all names that are declared anywhere in a module
+#[derive(Default)]
+struct DeclaredNames<'a> {
+    specifier: &'a str,
+    declared: HashSet<String>,
+    /// names imported from the helper specifier -> local name
+    imported: HashMap<String, String>,
+}
+
+impl Visit for DeclaredNames<'_> {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.declared.insert(ident.sym.to_string());
+    }
+
+    fn visit_fn_decl(&mut self, fn_decl: &FnDecl) {
+        self.declared.insert(fn_decl.ident.sym.to_string());
+        fn_decl.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, fn_expr: &FnExpr) {
+        if let Some(ident) = &fn_expr.ident {
+            self.declared.insert(ident.sym.to_string());
+        }
+        fn_expr.visit_children_with(self);
+    }
+
+    fn visit_class_decl(&mut self, class_decl: &ClassDecl) {
+        self.declared.insert(class_decl.ident.sym.to_string());
+        class_decl.visit_children_with(self);
+    }
+
+    fn visit_class_expr(&mut self, class_expr: &ClassExpr) {
+        if let Some(ident) = &class_expr.ident {
+            self.declared.insert(ident.sym.to_string());
+        }
+        class_expr.visit_children_with(self);
+    }
+
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        let from_specifier = import.src.value == *self.specifier && !import.type_only;
+        for specifier in &import.specifiers {
+            match specifier {
+                ImportSpecifier::Named(named) if from_specifier && !named.is_type_only => {
+                    let imported = match &named.imported {
+                        Some(ModuleExportName::Ident(i)) => i.sym.to_string(),
+                        Some(ModuleExportName::Str(s)) => s.value.to_string(),
+                        None => named.local.sym.to_string(),
+                    };
+                    self.imported.insert(imported, named.local.sym.to_string());
+                }
+                _ => {}
+            }
+            let local = match specifier {
+                ImportSpecifier::Named(s) => &s.local,
+                ImportSpecifier::Default(s) => &s.local,
+                ImportSpecifier::Namespace(s) => &s.local,
+            };
+            self.declared.insert(local.sym.to_string());
+        }
+    }
+}
@@ -1,368 +1,1240 @@
-// use visitor::TransformVisitor;
+use swc_common::DUMMY_SP;
+use swc_core::ecma::ast::Program;
+use swc_core::ecma::visit::FoldWith;
+use swc_core::plugin::{
+    metadata::TransformPluginMetadataContextKind, metadata::TransformPluginProgramMetadata,
+    plugin_transform,
+};
 
+use config::{JusixConfig, TransformMetadata};
+
+pub mod config;
+pub mod constant;
+mod errors;
+mod imports;
+pub mod manifest;
+pub mod pass;
+pub mod scope;
+pub mod transform;
 pub mod visitor;
 
-// #[plugin_transform]
-// pub fn process_transform(program: Program, _metadata: TransformPluginProgramMetadata) -> Program {
-//     program.fold_with(&mut TransformVisitor)
-// }
-
-// An example to test plugin transform.
-// Recommended strategy to test plugin's transform is verify
-// the Visitor's behavior, instead of trying to run `process_transform` with mocks
-// unless explicitly required to do so.
-// test!(
-//     Default::default(),
-//     |_| TransformVisitor,
-//     t1,
-//     r#"const x = $(10)"#
-// );
-
-// test!(
-//     Default::default(),
-//     |_| TransformVisitor,
-//     t2,
-//     r#"const y = $(y * 2)"#
-// );
-
-// test!(
-//     Default::default(),
-//     |_| TransformVisitor,
-//     t3,
-//     r#"run(() => {
-//         console.log(x + y);
-//         return x + 1;
-//     })"#
-// );
-
-// test!(
-//     Default::default(),
-//     |_| TransformVisitor,
-//     t4,
-//     r#"run(() => x + 1)"#
-// );
-
-// test!(
-//     Default::default(),
-//     |_| TransformVisitor,
-//     t5,
-//     r#"run(() => {
-//         use(x);
-//         return x + y;
-//     })"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t6,
-//     r#"<button onclick:frontend={() => console.log(x)} />"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t7,
-//     r#"<button value={x+1} />"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t8,
-//     r#"<button value:frontend={x+1} />"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t9,
-//     r#"<button value:frontend={always(() => x+1)} />"#
-// );
-
-// test!(
-//     Default::default(),
-//     |_| TransformVisitor,
-//     t10,
-//     r#"normalCallback(() => {
-//         return x + y;
-//     })"#
-// );
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t11,
-//     r#"<div>{ x + 1 }</div>"#
-// );
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t12,
-//     r#"<div>
-//         <span>{ x + 1 }</span>
-//         <span>{ y + 1 }</span>
-//         <span>X + Y = { x + y }</span>
-//     </div>"#
-// );
-
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t13,
-//     r#"<div>
-//         {
-//             x ? 
-//                 <span>{ x + 1 }</span> : 
-//                 <span>False</span>
-//         }
-//     </div>"#
-// );
-
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t14,
-//     r#"<div>{x.title}</div>"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t15,
-//     r#"<input value={x.name}/>"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t16,
-//     r#"<input value={x.$.name} id={x.$$.name}/>"#
-// );
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t17,
-//     r#"<input value={x['äü']}/>"#
-// );
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t18,
-//     r#"<div>
-//         {
-//             array.map((item) => {
-//                 return <span>{item}</span>
-//             })
-//         }
-//     </div>
-//     "#
-// );
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t19,
-//     r#"<div>
-//         {
-//             array.map((item) => item)
-//         }
-//         {
-//             array.map((item) => item * x)
-//         }
-//          {
-//             array.map((item) => {
-//                 return item * x;    
-//             })
-//         }
-//         {
-//             array.map((item) => {
-//                 return <span>{item * x}</span>
-//             })
-//         }
-//         {
-//             array.map((item) => <div>{item * x}</div>)
-//         }
-//         {
-//             array.filter((item) => {
-//                 return <span>{item * x}</span>
-//             })
-//         }
-//          {
-//             array.normalMethod((item) => {
-//                 return <span>{item * 2}</span>
-//             })
-//         }
-//     </div>
-//     "#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t20,
-//     r#"<input value={x[0]}/>"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t21,
-//     r#"<input value={x.y.z[0]}/>"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t22,
-//     r#"const x = arr.map(a => a*2)"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t23,
-//     r#"const x = $(arr.map(a => a*2))"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t24,
-//     r#"const x = $(() => x + 1)"#
-// );
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t25,
-//     r#"const x = $(x.$.y)"#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t26,
-//     r#"
-//     const x = <div>{x+1}</div>;
-//     const x = $(<div>{x+1}</div>);
-//     "#
-// );
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t27,
-//     r#"
-//     const x = $([
-//         1,2,y+1
-//     ])
-//     "#
-// );
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t28,
-//     r#"
-//     const x = $([
-//         1,2,3
-//     ])
-//     "#
-// );
-
-
-// test!(
-//     Syntax::Es(EsSyntax {
-//         jsx: true,
-//         ..Default::default()
-//     },),
-//     |_| TransformVisitor,
-//     t29,
-//     r#"
-//     export default <div>
-//         Count is {count + 1}
-//     </div>;
-//     "#
-// );
+pub use pass::{pass, pass_with_metadata};
+pub use transform::{
+    transform, Diagnostic, Diagnostics, SourceSyntax, TransformOptions, TransformOutput,
+};
+
+#[plugin_transform]
+pub fn process_transform(program: Program, metadata: TransformPluginProgramMetadata) -> Program {
+    let config = match JusixConfig::from_json(
+        &metadata.get_transform_plugin_config().unwrap_or_default(),
+    ) {
+        Ok(config) => config,
+        Err(e) => {
+            errors::error(DUMMY_SP, &format!("Invalid jusix plugin config: {}", e));
+            return program;
+        }
+    };
+
+    let transform_metadata = TransformMetadata {
+        filename: metadata.get_context(&TransformPluginMetadataContextKind::Filename),
+        env_name: metadata.get_context(&TransformPluginMetadataContextKind::Env),
+        module_path: None,
+    };
+
+    program.fold_with(&mut pass_with_metadata(config, transform_metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use visitor::TransformVisitor;
+    use swc_ecma_parser::{EsSyntax, Syntax};
+    use swc_core::ecma::transforms::testing::test;
+
+    // column and message of the warnings of a transform
+    fn warnings(output: &TransformOutput) -> Vec<(usize, &str)> {
+        output.warnings.iter().map(|w| (w.column, w.message.as_str())).collect()
+    }
+
+    // Recommended strategy to test plugin's transform is verify
+    // the Visitor's behavior, instead of trying to run `process_transform` with mocks
+    // unless explicitly required to do so.
+    test!(
+        Default::default(),
+        |_| TransformVisitor::default(),
+        t1,
+        r#"const x = $(10)"#
+    );
+
+    test!(
+        Default::default(),
+        |_| TransformVisitor::default(),
+        t2,
+        r#"const y = $(y * 2)"#
+    );
+
+    test!(
+        Default::default(),
+        |_| TransformVisitor::default(),
+        t3,
+        r#"run(() => {
+        console.log(x + y);
+        return x + 1;
+    })"#
+    );
+
+    test!(
+        Default::default(),
+        |_| TransformVisitor::default(),
+        t4,
+        r#"run(() => x + 1)"#
+    );
+
+    test!(
+        Default::default(),
+        |_| TransformVisitor::default(),
+        t5,
+        r#"run(() => {
+        use(x);
+        return x + y;
+    })"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t6,
+        r#"<button onclick:frontend={() => console.log(x)} />"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t7,
+        r#"<button value={x+1} />"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t8,
+        r#"<button value:frontend={x+1} />"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t9,
+        r#"<button value:frontend={always(() => x+1)} />"#
+    );
+
+    test!(
+        Default::default(),
+        |_| TransformVisitor::default(),
+        t10,
+        r#"normalCallback(() => {
+        return x + y;
+    })"#
+    );
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t11,
+        r#"<div>{ x + 1 }</div>"#
+    );
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t12,
+        r#"<div>
+        <span>{ x + 1 }</span>
+        <span>{ y + 1 }</span>
+        <span>X + Y = { x + y }</span>
+    </div>"#
+    );
+
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t13,
+        r#"<div>
+        {
+            x ? 
+                <span>{ x + 1 }</span> : 
+                <span>False</span>
+        }
+    </div>"#
+    );
+
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t14,
+        r#"<div>{x.title}</div>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t15,
+        r#"<input value={x.name}/>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t16,
+        r#"<input value={x.$.name} id={x.$$.name}/>"#
+    );
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t17,
+        r#"<input value={x['äü']}/>"#
+    );
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t18,
+        r#"<div>
+        {
+            array.map((item) => {
+                return <span>{item}</span>
+            })
+        }
+    </div>
+    "#
+    );
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t19,
+        r#"<div>
+        {
+            array.map((item) => item)
+        }
+        {
+            array.map((item) => item * x)
+        }
+         {
+            array.map((item) => {
+                return item * x;    
+            })
+        }
+        {
+            array.map((item) => {
+                return <span>{item * x}</span>
+            })
+        }
+        {
+            array.map((item) => <div>{item * x}</div>)
+        }
+        {
+            array.filter((item) => {
+                return <span>{item * x}</span>
+            })
+        }
+         {
+            array.normalMethod((item) => {
+                return <span>{item * 2}</span>
+            })
+        }
+    </div>
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t20,
+        r#"<input value={x[0]}/>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t21,
+        r#"<input value={x.y.z[0]}/>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t22,
+        r#"const x = arr.map(a => a*2)"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t23,
+        r#"const x = $(arr.map(a => a*2))"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t24,
+        r#"const x = $(() => x + 1)"#
+    );
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t25,
+        r#"const x = $(x.$.y)"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t26,
+        r#"
+    const x = <div>{x+1}</div>;
+    const x = $(<div>{x+1}</div>);
+    "#
+    );
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t27,
+        r#"
+    const x = $([
+        1,2,y+1
+    ])
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t28,
+        r#"
+    const x = $([
+        1,2,3
+    ])
+    "#
+    );
+
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t29,
+        r#"
+    export default <div>
+        Count is {count + 1}
+    </div>;
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig {
+                helpers: config::HelperNames {
+                    dollar: "reactive".into(),
+                    always: "always".into(),
+                    pointer: "pointer".into(),
+                    prop: "property".into(),
+                    use_: "capture".into(),
+                    run: "remote".into(),
+                    if_: "when".into(),
+                    template: "reactiveTemplate".into(),
+                    iterate: "iterate".into(),
+                },
+                ..Default::default()
+            },
+            Default::default()
+        ),
+        t30,
+        r#"
+    const x = reactive(10);
+    const y = reactive(x * 2);
+    const $ = jQuery;
+    remote(() => x + y);
+    <div value={x.name}>{x + 1}{$(x)}</div>;
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig {
+                import_source: Some("datex".into()),
+                ..Default::default()
+            },
+            Default::default()
+        ),
+        t31,
+        r#"
+    import { $$ as pointer } from "datex";
+    const prop = "local";
+    const x = $(10);
+    <div value={x.name}>{x + 1}</div>;
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig {
+                import_source: Some("datex".into()),
+                ..Default::default()
+            },
+            Default::default()
+        ),
+        t32,
+        r#"
+    const label = "static";
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t33,
+        r#"
+    run((event, { detail = fallback }) => {
+        const local = event.value + offset;
+        let [first, ...rest] = list;
+        function helper(y) { return y * factor; }
+        for (const item of items) { total += item; }
+        try { save({ local, first, rest }); } catch (error) { console.error(error, errorMessage); }
+        {
+            const offset = 1;
+            count = helper(offset);
+        }
+        return <Component value={local}>{detail}</Component>;
+    })
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t34,
+        r#"
+    import $ from "jquery";
+    function Run(fn) { return fn(); }
+    const a = $(x + 1);
+    Run(() => console.log(y));
+    function list($, run) {
+        return [$(x + 1), run(() => y)];
+    }
+    {
+        const run = (fn) => fn();
+        run(() => y);
+    }
+    try {} catch (run) { run(() => y); }
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t35,
+        r#"
+    import { $ as reactive, run as transfer } from "datex-core-legacy/datex.ts";
+    const a = reactive(x + 1);
+    transfer(() => y);
+    const b = $(x * 2);
+    function inner(reactive) {
+        return reactive(x);
+    }
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t36,
+        r#"
+    const a = $(1 + 2);
+    const b = $({ name: "x", tags: ["a", "b"] });
+    const c = $({ get name() { return x; } });
+    <div title={"a" + "b"} count={-1}>
+        {1 + 2}
+        {`static`}
+        {`a${1 + 1}`}
+        {[1, 2]}
+        {true ? "yes" : "no"}
+        {`value: ${x}`}
+        {x + 1}
+        {cond ? 1 : 2}
+    </div>
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            import_attributes: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t37,
+        r#"
+    import strings from "./strings.json" with { type: "json" };
+    import { format } from "./format.ts";
+    const LABEL = "Total";
+    export const SEPARATOR = LABEL + ": ";
+    const COUNT = $(0);
+    let mutable = "x";
+    const label = $(LABEL + SEPARATOR);
+    <div title={strings.title + SEPARATOR}>
+        {LABEL + SEPARATOR}
+        {`${LABEL} (${COUNT})`}
+        {mutable + LABEL}
+        {format + LABEL}
+    </div>;
+    function Item(LABEL) {
+        return <span>{LABEL + SEPARATOR}</span>;
+    }
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig {
+                precise: true,
+                ..Default::default()
+            },
+            Default::default()
+        ),
+        t38,
+        r#"
+    import { user } from "./state.ts";
+    const count = $(0);
+    const doubled = always(() => count * 2);
+    const alias = count;
+    let plain = 5;
+    function Counter({ step }, items) {
+        const local = $(step);
+        const label = "Step " + step;
+        return <div title={label + "!"} user={user.name} count={count + 1}>
+            {step * 2}
+            {local + step}
+            {doubled + plain}
+            {alias.value}
+            {items.map((item) => <li>{item.name + count}</li>)}
+            {$(plain * 2)}
+        </div>;
+    }
+    function Shadowed(count) {
+        return <div>{count + 1}</div>;
+    }
+    "#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t39,
+        r#"<div title={x?.user?.name} label={x.profile?.["label"]}>
+        {x?.user.name}
+        {x?.user?.getName()}
+    </div>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            decorators: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t40,
+        r#"@template()
+    class Counter extends Component {
+        #secret = $(0);
+        count = $(0);
+        render() {
+            return <div title={this.title} data-secret={this.#secret.value}>
+                {this.count}
+                {this.#secret}
+                {this.items[0]?.label}
+            </div>;
+        }
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t41,
+        r#"
+    const FIRST = 0;
+    <div title={x[index]} label={x[index].label}>
+        {x[FIRST]}
+        {x["first" + "Name"]}
+        {x.items[x.selected]}
+    </div>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig {
+                precise: true,
+                ..Default::default()
+            },
+            Default::default()
+        ),
+        t42,
+        r#"
+    const items = $([]);
+    const selected = $(0);
+    function Item({ index }) {
+        return <div title={items[index].title}>{items[selected]}</div>;
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig {
+                import_source: Some("uix".into()),
+                ..Default::default()
+            },
+            Default::default()
+        ),
+        t43,
+        r#"<div title={loggedIn ? "Logout" : "Login"}>
+        {loggedIn && <Profile user={user.name}/>}
+        {error || <span>No errors</span>}
+        {user.nickname ?? user.name}
+        {loading ? <Spinner/> : items.length ? <List items={items}/> : <>Empty</>}
+    </div>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t44,
+        r#"<div class={`card ${theme.color} ${active ? "active" : ""}`} style={`width: ${width}px`}>
+        {`Hello ${user.name}, you have ${items.length + 1} items`}
+        {`static ${1 + 1}`}
+    </div>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t45,
+        r#"<div title={tags.slice(0, 3).join(", ")} count={todos.filter(t => t.done).length}>
+        {todos.filter((t) => !t.done).sort((a, b) => a.date - b.date).map((t) => <li>{t.title}</li>)}
+        {todos.find(t => t.id == selected)}
+        {todos.some(t => t.done) && <button>Clear</button>}
+        {todos.$.map(t => <li>{t.title}</li>)}
+        {todos.length}
+    </div>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig::from_json(r#"{
+                "precise": true,
+                "collectionMethods": { "map": { "callbacks": [0] }, "toSorted": { "callbacks": [0] } }
+            }"#).unwrap(),
+            Default::default()
+        ),
+        t46,
+        r#"
+    const todos = $([]);
+    function List({ items }) {
+        return <ul>
+            {todos.toSorted((a, b) => a.date - b.date).map(t => <li>{t.title + todos.length}</li>)}
+            {items.map(i => <li>{i}</li>)}
+            {todos.filter(t => t.done)}
+        </ul>;
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t47,
+        r#"
+    const tags = $(new Set(["a", "b"]));
+    const users = new Map();
+    const settings = { keys: () => [] };
+    <div>
+        {Object.entries(form).map(([k, v]) => <li>{k}: {v.label}</li>)}
+        {Object.keys(form)}
+        {Array.from(tags, (tag) => <span>{tag.name}</span>)}
+        {Array.from(tags)}
+        {[...tags].length}
+        {users.values().filter(u => u.active)}
+        {tags.entries()}
+        {settings.keys()}
+    </div>;
+    function List({ Object }) {
+        return <ul>{Object.keys(form).map(k => <li>{k}</li>)}</ul>;
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t48,
+        r#"<div>
+        <ul>{todos.map(todo => <li key={todo.id}>{todo.title}</li>)}</ul>
+        <ul>{Object.entries(form).map(([name, value], i) => {
+            const label = name + ":";
+            return <li key={name}>{label} {value}</li>;
+        })}</ul>
+        <ul>{todos.filter(t => t.done).map(t => <li key="static">{t.title}</li>)}</ul>
+    </div>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig::from_json(r#"{ "plainAttributes": ["key", "stylesheet"] }"#).unwrap(),
+            Default::default()
+        ),
+        t49,
+        r#"<form
+        onsubmit={handler.bind(this)}
+        onClick={handlers.save}
+        oninput={makeHandler(id)}
+        ref={setEl}
+        stylesheet={theme.styles}
+        value={form.name}
+        open={state.open}
+        online={state.online}
+        one={x.y}
+        onboarding={steps.length > 0}
+    />"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t50,
+        r#"
+    import { effect as onChange } from "uix";
+    import { ref } from "vue";
+    const a = $(always(() => x + 1));
+    const b = $(prop(form, "name"));
+    function Field({ val }) {
+        return <div title={always(() => x + 1)} value={prop(form, "name")} state={val(x)}>
+            {always(() => x + 1)}
+            {onChange(() => console.log(x))}
+            {ref(x)}
+            {effect(() => x)}
+        </div>;
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t51,
+        r#"
+    <p>
+        {loggedIn ? user.name : "guest"}
+        {open ? items.map(i => <li>{i.name}</li>) : null}
+        {count || x + 1}
+    </p>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t52,
+        r#"
+    function Tag({ label, name }) {
+        return <p title={label.slice(0, 3)} size={label.length}>
+            {name.split(",").join(" ")}
+            {name.split(",").map(part => <b>{part.trim()}</b>)}
+            {[first, second].filter(x => x.visible)}
+            {items.filter(i => i.visible).length}
+        </p>;
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t53,
+        r#"<div>
+        <ul>{todos.map(t => {
+            const id = t.id;
+            return <li key={id}>{t.title}</li>;
+        })}</ul>
+        <ul>{todos.map(t => <li key={prefix + t.id}>{t.title}</li>)}</ul>
+        <ul>{todos.map(function (t) { return <li key={this.id}>{t.title}</li>; }, ctx)}</ul>
+        <ul>{todos.map(t => <li key={t.id}>{t.title}</li>, ctx)}</ul>
+        {todos.find(t => <li key={t.id}>{t.title}</li>)}
+    </div>"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t54,
+        r#"
+    const location = "/chat";
+    function Chat() {
+        const history = $([]);
+        const prompt = "Hi";
+        const self = this;
+        const prop = (x) => x;
+        return <button onclick:frontend={() => {
+            history.push(prompt);
+            self.update(prop(location));
+            console.log(document.title);
+        }}/>;
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::new(
+            JusixConfig {
+                precise: true,
+                ..Default::default()
+            },
+            Default::default()
+        ),
+        t55,
+        r#"
+    class Counter extends Component {
+        count = $(0);
+        items = $([]);
+        render() {
+            const format = function (n) { return this.prefix + n; };
+            return <div title={this.count + 1} label={format(2)}>
+                {this.items.length}
+                {this.items.map(item => <li>{item.name}</li>)}
+                {this.count}
+            </div>;
+        }
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t56,
+        r#"
+    const todos = $([]);
+    const names = new Array(3);
+    function List({ label }) {
+        return <div title={todos.slice(0, 3).join(", ")} count={todos.length}>
+            {todos.slice(0, 3)}
+            {todos.includes(selected)}
+            {names.length}
+            {label.slice(0, 3)}
+            {label.length}
+            {todos.filter(t => t.done).length}
+        </div>;
+    }"#
+    );
+
+    test!(
+        Syntax::Es(EsSyntax {
+            jsx: true,
+            ..Default::default()
+        },),
+        |_| TransformVisitor::default(),
+        t57,
+        r#"<div onmessage={h.msg} onmessageerror={h.error} ononline={h.online} online={state.online}>
+        <video onprogress={h.progress} oncanplay={h.ready} onseeking={h.seek} onloadstart={h.start} onwaiting={h.wait}/>
+        <details onbeforetoggle={h.toggle}/>
+        <form onformdata={h.data}/>
+    </div>"#
+    );
+
+    #[test]
+    fn config_rejects_unknown_keys() {
+        assert_eq!(JusixConfig::from_json("").unwrap(), JusixConfig::default());
+        assert_eq!(JusixConfig::from_json("{}").unwrap(), JusixConfig::default());
+        assert_eq!(
+            JusixConfig::from_json(r#"{ "frontendNamespace": "client" }"#)
+                .unwrap()
+                .frontend_namespace,
+            "client"
+        );
+        assert!(JusixConfig::from_json(r#"{ "frontendNamespce": "client" }"#)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown field `frontendNamespce`"));
+    }
+
+    #[test]
+    fn transform_source() {
+        let output = transform(
+            "const Counter = (props: { count: number }) => <div>{props.count + 1}</div>;",
+            "counter.tsx",
+            &TransformOptions {
+                source_map: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            output.code,
+            "const Counter = (props: {\n    count: number;\n})=><div>{_$(()=>props.count + 1)}</div>;\n"
+        );
+        assert!(output.map.unwrap().contains("\"mappings\""));
+
+        let err = transform("<div>{</div>", "broken.jsx", &Default::default()).unwrap_err();
+        assert_eq!(err.0[0].filename, "broken.jsx");
+        assert_eq!(err.0[0].line, 1);
+    }
+
+    #[test]
+    fn source_map_points_at_wrapped_expressions() {
+        let source = "const Counter = () =>\n    <p class={theme.color}>{count + 1}</p>;";
+        let output = transform(
+            source,
+            "counter.jsx",
+            &TransformOptions {
+                source_map: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let map = sourcemap::SourceMap::from_slice(output.map.unwrap().as_bytes()).unwrap();
+
+        // returns the (line, column) in the source that generated code starting with `generated` maps to
+        let source_position = |generated: &str| {
+            let (line, text) = output
+                .code
+                .lines()
+                .enumerate()
+                .find(|(_, line)| line.contains(generated))
+                .unwrap();
+            let token = map
+                .lookup_token(line as u32, text.find(generated).unwrap() as u32)
+                .unwrap();
+            (token.get_src_line(), token.get_src_col())
+        };
+        assert_eq!(output.code, "const Counter = ()=><p class={prop(theme, \"color\")}>{_$(()=>count + 1)}</p>;\n");
+        assert_eq!(source_position("prop("), (1, 14));
+        assert_eq!(source_position("_$("), (1, 28));
+    }
+
+    #[test]
+    fn list_items_without_key() {
+        let output = transform(
+            "<ul>{todos.map(t => <li>{t.title}</li>)}{todos.map(t => <><b/></>)}{todos.map(t => t.title)}</ul>",
+            "list.jsx",
+            &Default::default(),
+        )
+        .unwrap();
+        let message = "Elements rendered by map() should have a `key` attribute, so that they can be reordered instead of recreated";
+        assert_eq!(warnings(&output), [(21, message), (57, message)]);
+    }
+
+    #[test]
+    fn list_keys_with_callback_variables() {
+        let output = transform(
+            "<ul>{todos.map(t => { const id = t.id; return <li key={id}>{t.title}</li>; })}</ul>",
+            "list.jsx",
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            warnings(&output),
+            [(56, "The key uses `id`, which is declared in the map() callback, elements are recreated instead of reordered")]
+        );
+        assert!(output.code.contains("return <li key={id}>{prop(t, \"title\")}</li>;\n})}"));
+    }
+
+    #[test]
+    fn reactivity_manifest() {
+        let store = transform(
+            r#"
+            export const count = $(0);
+            export const LABEL = "Total";
+            const doubled = always(() => count * 2);
+            export { doubled, LABEL as label };
+            "#,
+            "src/store.ts",
+            &TransformOptions {
+                manifest: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let manifest = manifest::ReactivityManifest::from_json(&store.manifest.unwrap()).unwrap();
+        assert_eq!(
+            manifest.exports.into_iter().collect::<Vec<_>>(),
+            [
+                ("LABEL".to_string(), false),
+                ("count".to_string(), true),
+                ("doubled".to_string(), true),
+                ("label".to_string(), false),
+            ]
+        );
+
+        // the manifest of store.ts marks LABEL as non-reactive
+        let dir = std::env::temp_dir().join(format!("jusix-manifests-{}", std::process::id()));
+        let path = manifest::manifest_path(&dir, std::path::Path::new("src/store.ts"));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, r#"{ "exports": { "count": true, "LABEL": false } }"#).unwrap();
+
+        let view = transform(
+            r#"import { count, LABEL, other } from "./store.ts"; <div>{count + 1}{LABEL + "!"}{other + 1}</div>;"#,
+            "src/view.tsx",
+            &TransformOptions {
+                config: JusixConfig {
+                    precise: true,
+                    manifest_dir: Some(dir.to_string_lossy().into_owned()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(view.code.contains(r#"{_$(()=>count + 1)}{LABEL + "!"}{_$(()=>other + 1)}"#));
+    }
+
+    #[test]
+    fn pass_rejects_lowered_jsx() {
+        let err = transform(
+            r#"import { jsx as _jsx } from "uix/jsx-runtime"; _jsx("div", { children: x + 1 });"#,
+            "lowered.js",
+            &Default::default(),
+        )
+        .unwrap_err();
+        assert!(err.0[0].message.contains("jusix::pass must run before the JSX transform"));
+    }
+
+    #[test]
+    fn pass_accepts_handwritten_jsx_calls() {
+        let output = transform(
+            r#"import { jsx } from "uix/jsx-runtime"; export const el = jsx("div", { children: x + 1 });"#,
+            "elements.js",
+            &Default::default(),
+        )
+        .unwrap();
+        assert!(output.code.contains("jsx(\"div\""));
+    }
+
+    #[test]
+    fn script_with_import_source() {
+        let options = TransformOptions {
+            config: JusixConfig {
+                import_source: Some("uix".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = transform("const el = <div>{x + 1}{y.z}</div>;", "script.jsx", &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "script.jsx:1:1: `_$`, `prop` can't be imported from \"uix\" into a script, add an import or export to make the file a module"
+        );
+
+        // nothing to import
+        let output = transform("const label = \"static\";", "script.js", &options).unwrap();
+        assert_eq!(output.code, "const label = \"static\";\n");
+    }
+
+    #[test]
+    fn unsupported_input_diagnostics() {
+        let err = transform("const x = $();
+const y = $(...values);", "dollar.js", &Default::default())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "dollar.js:1:11: $() expects a value to make reactive\n\
+             dollar.js:2:13: Spread arguments are not supported in $()"
+        );
+
+        let err = transform(r#"<button onclick:frontend={<b/>} onhover:frontend/>"#, "frontend.jsx", &Default::default())
+            .unwrap_err();
+        assert_eq!(err.0.len(), 2);
+        assert_eq!((err.0[0].line, err.0[0].column), (1, 27));
+        assert!(err.0[0].message.starts_with("Invalid :frontend value"));
+        assert!(err.0[1].message.starts_with("Missing :frontend value"));
 
+        // unsupported nodes are left unchanged
+        let output = transform(
+            "class A { #x = 1; render() { return <div onclick:frontend={function () {}}>{this.x}{$(a, b)}</div>; } }",
+            "warnings.jsx",
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            output.code,
+            "class A {\n    #x = 1;\n    render() {\n        return <div onclick:frontend={function() {}}>{prop(this, \"x\")}{$(a, b)}</div>;\n    }\n}\n"
+        );
+        assert_eq!(
+            warnings(&output),
+            [
+                (60, "Transferable closures must be arrow functions, the function is not transformed"),
+                (90, "$() only takes one argument, the call is not transformed"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Extension of manifest files, appended to the module path (`store.ts.reactivity.json`)
+pub const MANIFEST_EXTENSION: &str = "reactivity.json";
+
+/// Lists the exports of a module and whether they hold reactive values.
+///
+/// Written for each transformed module and read when transforming modules that import it,
+/// so that precise mode can tell reactive imports (`export const count = $(0)`)
+/// from plain ones (`export const LABEL = "Total"`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReactivityManifest {
+    /// export name -> reactive
+    pub exports: BTreeMap<String, bool>,
+}
+
+impl ReactivityManifest {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("manifest is serializable")
+    }
+
+    /// Reads the manifest of `module` from `dir`, see [`manifest_path`]
+    pub fn read(dir: &Path, module: &Path) -> Option<Self> {
+        let json = fs::read_to_string(manifest_path(dir, module)).ok()?;
+        Self::from_json(&json).ok()
+    }
+}
+
+/// Path of the manifest for `module` inside the manifest directory `dir`.
+///
+/// The directory mirrors the module paths, e.g. the manifest of `src/store.ts` is
+/// `<dir>/src/store.ts.reactivity.json`. Absolute module paths are taken relative
+/// to the working directory.
+pub fn manifest_path(dir: &Path, module: &Path) -> PathBuf {
+    let mut path = dir.join(module_key(module)).into_os_string();
+    path.push(".");
+    path.push(MANIFEST_EXTENSION);
+    path.into()
+}
+
+/// Resolves a relative import specifier (`./store.ts`) against the path of the importing
+/// module. Returns `None` for bare specifiers and URLs, which have no manifest.
+pub fn resolve_import(importer: &str, specifier: &str) -> Option<PathBuf> {
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return None;
+    }
+    let dir = Path::new(importer).parent().unwrap_or(Path::new(""));
+    Some(dir.join(specifier))
+}
+
+/// Normalized, relative form of a module path that is used as manifest location
+fn module_key(module: &Path) -> PathBuf {
+    let module = match env::current_dir() {
+        Ok(cwd) if module.is_absolute() => module.strip_prefix(&cwd).unwrap_or(module),
+        _ => module,
+    };
+
+    let mut key = PathBuf::new();
+    for component in module.components() {
+        match component {
+            Component::Normal(part) => key.push(part),
+            Component::ParentDir => {
+                key.pop();
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    key
+}
@@ -0,0 +1,110 @@
+use swc_common::Span;
+use swc_ecma_ast::{ImportDecl, JSXElement, JSXFragment, Module, Script};
+use swc_ecma_visit::{Fold, FoldWith, Visit, VisitWith};
+
+use crate::config::{JusixConfig, TransformMetadata};
+use crate::errors;
+use crate::visitor::TransformVisitor;
+
+/// Creates the jusix pass for use in a swc pass pipeline (e.g. in `deno_ast`).
+///
+/// Required ordering:
+/// - **before** the JSX lowering (`swc_ecma_transforms_react::react`/`jsx`), because
+///   jusix rewrites JSX expression containers. The pass reports an error and leaves the
+///   module unchanged if it finds no JSX, but an import from a `jsx-runtime` module with
+///   the aliases generated by the JSX transform (`jsx as _jsx`), which means the JSX was
+///   already lowered.
+/// - before **or** after TypeScript stripping, jusix does not depend on type annotations.
+///
+/// ```ignore
+/// let program = program.fold_with(&mut chain!(
+///     typescript::strip(unresolved_mark, top_level_mark),
+///     jusix::pass(config),
+///     react::<SingleThreadedComments>(cm, Some(comments), options, top_level_mark, unresolved_mark),
+/// ));
+/// ```
+pub fn pass(config: JusixConfig) -> impl Fold {
+    pass_with_metadata(config, TransformMetadata::default())
+}
+
+/// Same as [`pass`], with information about the module that is being transformed
+pub fn pass_with_metadata(config: JusixConfig, metadata: TransformMetadata) -> impl Fold {
+    JusixPass::new(config, metadata)
+}
+
+pub(crate) struct JusixPass {
+    visitor: TransformVisitor,
+}
+
+impl JusixPass {
+    pub(crate) fn new(config: JusixConfig, metadata: TransformMetadata) -> Self {
+        JusixPass {
+            visitor: TransformVisitor::new(config, metadata),
+        }
+    }
+
+    pub(crate) fn visitor(&self) -> &TransformVisitor {
+        &self.visitor
+    }
+}
+
+impl Fold for JusixPass {
+    fn fold_module(&mut self, module: Module) -> Module {
+        if is_jsx_lowered(&module) {
+            return module;
+        }
+        module.fold_with(&mut self.visitor)
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        script.fold_with(&mut self.visitor)
+    }
+}
+
+// reports an error if the module was already processed by the JSX transform
+fn is_jsx_lowered(module: &Module) -> bool {
+    let mut finder = JsxFinder::default();
+    module.visit_with(&mut finder);
+    match finder.jsx_runtime_import.filter(|_| !finder.has_jsx) {
+        Some((src, span)) => {
+            errors::error(
+                span,
+                &format!("jusix::pass must run before the JSX transform, but the module already imports \"{}\"", src),
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+// `_jsx`, `_jsxs`, `_jsxDEV` and `_Fragment`
+fn is_generated_jsx_alias(local: &str) -> bool {
+    local.starts_with("_jsx") || local == "_Fragment"
+}
+
+#[derive(Default)]
+struct JsxFinder {
+    has_jsx: bool,
+    jsx_runtime_import: Option<(String, Span)>,
+}
+
+impl Visit for JsxFinder {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        let src = import.src.value.as_str();
+        // the JSX transform imports the runtime with generated aliases (`jsx as _jsx`),
+        // modules that call `jsx()` by hand are valid input
+        if (src.ends_with("/jsx-runtime") || src.ends_with("/jsx-dev-runtime"))
+            && import.specifiers.iter().any(|s| is_generated_jsx_alias(&s.local().sym))
+        {
+            self.jsx_runtime_import = Some((src.to_string(), import.span));
+        }
+    }
+
+    fn visit_jsx_element(&mut self, _: &JSXElement) {
+        self.has_jsx = true;
+    }
+
+    fn visit_jsx_fragment(&mut self, _: &JSXFragment) {
+        self.has_jsx = true;
+    }
+}
@@ -0,0 +1,645 @@
+use std::collections::{HashMap, HashSet};
+
+use swc_atoms::Atom;
+use swc_ecma_ast::{
+    ArrowExpr, BindingIdent, BlockStmt, BlockStmtOrExpr, BreakStmt, Callee, CatchClause, Class, ClassDecl, ClassExpr,
+    Constructor, ContinueStmt, Decl, DefaultDecl, ExportDecl, Expr, FnDecl, FnExpr, ForHead,
+    ForInStmt, ForOfStmt, ForStmt, Function, GetterProp, ImportDefaultSpecifier, ImportNamedSpecifier,
+    ImportStarAsSpecifier, JSXElementName, JSXObject, LabeledStmt, Module,
+    ModuleDecl, ModuleItem, ObjectPatProp, Param, Pat, Prop, PropName, SetterProp, SimpleAssignTarget, Stmt, ThisExpr, TsParamProp, TsParamPropParam, VarDecl, VarDeclKind,
+    VarDeclOrExpr, VarDeclarator,
+};
+use swc_ecma_visit::{Visit, VisitWith};
+
+/// Returns all variables that are referenced but not declared inside `node`,
+/// in order of their first reference.
+///
+/// Declarations are resolved with JavaScript scoping rules (`var` and function
+/// hoisting, block scoped `let`/`const`/`class`, parameters, catch clauses),
+/// so shadowed outer variables are not reported.
+pub fn free_variables<N: VisitWith<FreeVariables>>(node: &N) -> Vec<Atom> {
+    let mut collector = FreeVariables::default();
+    node.visit_with(&mut collector);
+    collector.free
+}
+
+/// Returns true if `expr` reads `this` of the surrounding function.
+///
+/// `this` inside nested functions, methods and classes refers to another object and is not reported.
+pub fn reads_this(expr: &Expr) -> bool {
+    let mut finder = ThisFinder::default();
+    expr.visit_with(&mut finder);
+    finder.found
+}
+
+#[derive(Default)]
+struct ThisFinder {
+    found: bool,
+}
+
+impl Visit for ThisFinder {
+    fn visit_this_expr(&mut self, _: &ThisExpr) {
+        self.found = true;
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+
+    fn visit_getter_prop(&mut self, _: &GetterProp) {}
+
+    fn visit_setter_prop(&mut self, _: &SetterProp) {}
+}
+
+/// Collects the binding names declared by a pattern
+pub fn pat_bindings(pat: &Pat, bindings: &mut Vec<Atom>) {
+    match pat {
+        Pat::Ident(i) => bindings.push(i.sym.clone()),
+        Pat::Array(a) => {
+            for elem in a.elems.iter().flatten() {
+                pat_bindings(elem, bindings);
+            }
+        }
+        Pat::Rest(r) => pat_bindings(&r.arg, bindings),
+        Pat::Object(o) => {
+            for prop in &o.props {
+                match prop {
+                    ObjectPatProp::KeyValue(kv) => pat_bindings(&kv.value, bindings),
+                    ObjectPatProp::Assign(a) => bindings.push(a.key.sym.clone()),
+                    ObjectPatProp::Rest(r) => pat_bindings(&r.arg, bindings),
+                }
+            }
+        }
+        Pat::Assign(a) => pat_bindings(&a.left, bindings),
+        Pat::Invalid(_) | Pat::Expr(_) => {}
+    }
+}
+
+/// Names declared by `let`/`const`/`class`/`function` directly inside a list of statements
+pub fn lexical_declarations<'a>(stmts: impl IntoIterator<Item = &'a Stmt>) -> Vec<Atom> {
+    let mut names = vec![];
+    for stmt in stmts {
+        if let Stmt::Decl(decl) = stmt {
+            lexical_decl_bindings(decl, &mut names);
+        }
+    }
+    names
+}
+
+/// Names declared at the top level of a module, including imports and exported declarations
+pub fn module_declarations(module: &Module) -> Vec<Atom> {
+    let mut collector = VarDeclarations::default();
+    module.visit_with(&mut collector);
+    let mut names = collector.names;
+
+    for item in &module.body {
+        match item {
+            ModuleItem::Stmt(Stmt::Decl(decl))
+            | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl, .. })) => {
+                lexical_decl_bindings(decl, &mut names)
+            }
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(export)) => match &export.decl {
+                DefaultDecl::Fn(FnExpr { ident: Some(i), .. })
+                | DefaultDecl::Class(ClassExpr { ident: Some(i), .. }) => names.push(i.sym.clone()),
+                _ => {}
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => {
+                for specifier in &import.specifiers {
+                    names.push(specifier.local().sym.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Collects the binding names declared by a declaration
+pub fn decl_bindings(decl: &Decl, names: &mut Vec<Atom>) {
+    match decl {
+        Decl::Var(v) => {
+            for d in &v.decls {
+                pat_bindings(&d.name, names);
+            }
+        }
+        Decl::Using(u) => {
+            for d in &u.decls {
+                pat_bindings(&d.name, names);
+            }
+        }
+        Decl::Fn(f) => names.push(f.ident.sym.clone()),
+        Decl::Class(c) => names.push(c.ident.sym.clone()),
+        Decl::TsEnum(e) => names.push(e.id.sym.clone()),
+        _ => {}
+    }
+}
+
+fn lexical_decl_bindings(decl: &Decl, names: &mut Vec<Atom>) {
+    match decl {
+        Decl::Var(v) if v.kind == VarDeclKind::Var => {}
+        _ => decl_bindings(decl, names),
+    }
+}
+
+/// Names declared in the scope of a function: parameters, hoisted `var`s and lexical declarations
+pub fn function_declarations<'a>(
+    params: impl IntoIterator<Item = &'a Pat>,
+    body: &[Stmt],
+) -> Vec<Atom> {
+    let mut names = vec![];
+    for param in params {
+        pat_bindings(param, &mut names);
+    }
+    names.extend(var_declarations(body));
+    names.extend(lexical_declarations(body));
+    names
+}
+
+/// Names declared by `var` anywhere inside a function body (excluding nested functions)
+pub fn var_declarations(stmts: &[Stmt]) -> Vec<Atom> {
+    let mut collector = VarDeclarations::default();
+    stmts.visit_with(&mut collector);
+    collector.names
+}
+
+#[derive(Default)]
+struct VarDeclarations {
+    names: Vec<Atom>,
+}
+
+impl Visit for VarDeclarations {
+    fn visit_var_decl(&mut self, var_decl: &VarDecl) {
+        if var_decl.kind == VarDeclKind::Var {
+            for d in &var_decl.decls {
+                pat_bindings(&d.name, &mut self.names);
+            }
+        }
+    }
+
+    // var declarations in nested functions belong to those functions
+    fn visit_function(&mut self, _: &Function) {}
+    fn visit_class(&mut self, _: &Class) {}
+    fn visit_expr(&mut self, _: &Expr) {}
+}
+
+/// Kind of collection that a binding always holds, see [`collection_bindings`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collection {
+    Array,
+    Map,
+    Set,
+}
+
+/// Names in `module` that always hold an array, `Map` or `Set`: every declaration of the name
+/// in the module is a `const` that is initialized with an array literal, `new Array(...)`,
+/// `new Map(...)` or `new Set(...)`, possibly wrapped in a call for which `is_wrapper`
+/// returns true (e.g. `$(new Map())`).
+pub fn collection_bindings(module: &Module, is_wrapper: &dyn Fn(&Expr) -> bool) -> HashMap<Atom, Collection> {
+    let mut collector = CollectionBindings {
+        is_wrapper,
+        names: HashMap::new(),
+    };
+    module.visit_with(&mut collector);
+    collector
+        .names
+        .into_iter()
+        .filter_map(|(name, collection)| Some((name, collection?)))
+        .collect()
+}
+
+struct CollectionBindings<'a> {
+    is_wrapper: &'a dyn Fn(&Expr) -> bool,
+    // name -> the collection that all declarations hold
+    names: HashMap<Atom, Option<Collection>>,
+}
+
+impl CollectionBindings<'_> {
+    fn declare(&mut self, name: &Atom, collection: Option<Collection>) {
+        let entry = self.names.entry(name.clone()).or_insert(collection);
+        if *entry != collection {
+            *entry = None;
+        }
+    }
+
+    fn collection(&self, init: &Expr) -> Option<Collection> {
+        match init.unwrap_parens() {
+            Expr::Array(_) => Some(Collection::Array),
+            Expr::New(new) => match &*new.callee {
+                Expr::Ident(i) if &*i.sym == "Array" => Some(Collection::Array),
+                Expr::Ident(i) if &*i.sym == "Map" => Some(Collection::Map),
+                Expr::Ident(i) if &*i.sym == "Set" => Some(Collection::Set),
+                _ => None,
+            },
+            Expr::Call(call) => match (&call.callee, call.args.as_slice()) {
+                (Callee::Expr(callee), [arg]) if (self.is_wrapper)(callee) && arg.spread.is_none() => {
+                    self.collection(&arg.expr)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Visit for CollectionBindings<'_> {
+    fn visit_var_decl(&mut self, var_decl: &VarDecl) {
+        for d in &var_decl.decls {
+            match (&d.name, &d.init) {
+                (Pat::Ident(name), Some(init)) if var_decl.kind == VarDeclKind::Const => {
+                    let collection = self.collection(init);
+                    self.declare(&name.sym, collection);
+                    init.visit_with(self);
+                }
+                _ => d.visit_with(self),
+            }
+        }
+    }
+
+    // all other bindings exclude the name
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.declare(&ident.sym, None);
+    }
+
+    fn visit_fn_decl(&mut self, fn_decl: &FnDecl) {
+        self.declare(&fn_decl.ident.sym, None);
+        fn_decl.function.visit_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, fn_expr: &FnExpr) {
+        if let Some(ident) = &fn_expr.ident {
+            self.declare(&ident.sym, None);
+        }
+        fn_expr.function.visit_with(self);
+    }
+
+    fn visit_class_decl(&mut self, class_decl: &ClassDecl) {
+        self.declare(&class_decl.ident.sym, None);
+        class_decl.class.visit_with(self);
+    }
+
+    fn visit_class_expr(&mut self, class_expr: &ClassExpr) {
+        if let Some(ident) = &class_expr.ident {
+            self.declare(&ident.sym, None);
+        }
+        class_expr.class.visit_with(self);
+    }
+
+    fn visit_import_named_specifier(&mut self, specifier: &ImportNamedSpecifier) {
+        self.declare(&specifier.local.sym, None);
+    }
+
+    fn visit_import_default_specifier(&mut self, specifier: &ImportDefaultSpecifier) {
+        self.declare(&specifier.local.sym, None);
+    }
+
+    fn visit_import_star_as_specifier(&mut self, specifier: &ImportStarAsSpecifier) {
+        self.declare(&specifier.local.sym, None);
+    }
+}
+
+/// Stack of the names declared in the scopes enclosing the node that is currently folded
+#[derive(Default)]
+pub struct Scopes {
+    stack: Vec<Scope>,
+}
+
+#[derive(Default)]
+struct Scope {
+    names: HashSet<Atom>,
+    // declared names that hold reactive values
+    reactive: HashSet<Atom>,
+}
+
+impl Scopes {
+    pub fn push(&mut self, names: impl IntoIterator<Item = Atom>) {
+        self.stack.push(Scope {
+            names: names.into_iter().collect(),
+            reactive: HashSet::new(),
+        });
+    }
+
+    pub fn pop(&mut self) {
+        self.stack.pop();
+    }
+
+    /// Returns true if `sym` is declared in any of the enclosing scopes
+    pub fn is_declared(&self, sym: &Atom) -> bool {
+        self.stack.iter().any(|scope| scope.names.contains(sym))
+    }
+
+    /// Marks bindings as reactive in the innermost scope that declares them
+    pub fn mark_reactive(&mut self, names: impl IntoIterator<Item = Atom>) {
+        for name in names {
+            if let Some(scope) = self.stack.iter_mut().rev().find(|s| s.names.contains(&name)) {
+                scope.reactive.insert(name);
+            }
+        }
+    }
+
+    /// Returns whether the innermost declaration of `sym` is reactive,
+    /// or `None` if `sym` is not declared in any of the enclosing scopes
+    pub fn is_reactive(&self, sym: &Atom) -> Option<bool> {
+        self.stack
+            .iter()
+            .rev()
+            .find(|scope| scope.names.contains(sym))
+            .map(|scope| scope.reactive.contains(sym))
+    }
+}
+
+/// Scope-aware collector for free variables, see [`free_variables`]
+#[derive(Default)]
+pub struct FreeVariables {
+    scopes: Vec<HashSet<Atom>>,
+    free: Vec<Atom>,
+}
+
+impl FreeVariables {
+    fn push_scope(&mut self, names: impl IntoIterator<Item = Atom>) {
+        self.scopes.push(names.into_iter().collect());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, names: impl IntoIterator<Item = Atom>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.extend(names);
+        }
+    }
+
+    fn reference(&mut self, sym: &Atom) {
+        if !self.scopes.iter().any(|scope| scope.contains(sym)) && !self.free.contains(sym) {
+            self.free.push(sym.clone());
+        }
+    }
+
+    /// Visits the parts of a declaration pattern that can contain references
+    /// (default values and computed keys), but not the declared bindings
+    fn visit_pat_references(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(_) | Pat::Invalid(_) => {}
+            Pat::Array(a) => {
+                for elem in a.elems.iter().flatten() {
+                    self.visit_pat_references(elem);
+                }
+            }
+            Pat::Rest(r) => self.visit_pat_references(&r.arg),
+            Pat::Object(o) => {
+                for prop in &o.props {
+                    match prop {
+                        ObjectPatProp::KeyValue(kv) => {
+                            if let PropName::Computed(c) = &kv.key {
+                                c.expr.visit_with(self);
+                            }
+                            self.visit_pat_references(&kv.value);
+                        }
+                        ObjectPatProp::Assign(a) => a.value.visit_with(self),
+                        ObjectPatProp::Rest(r) => self.visit_pat_references(&r.arg),
+                    }
+                }
+            }
+            Pat::Assign(a) => {
+                self.visit_pat_references(&a.left);
+                a.right.visit_with(self);
+            }
+            Pat::Expr(e) => e.visit_with(self),
+        }
+    }
+
+    fn visit_params<'a>(&mut self, params: impl IntoIterator<Item = &'a Pat> + Clone) {
+        let mut names = vec![];
+        for pat in params.clone() {
+            pat_bindings(pat, &mut names);
+        }
+        self.declare(names);
+        for pat in params {
+            self.visit_pat_references(pat);
+        }
+    }
+
+    /// Visits the statements of a function body, with hoisted declarations
+    fn visit_function_body(&mut self, stmts: &[Stmt]) {
+        self.declare(var_declarations(stmts));
+        self.declare(lexical_declarations(stmts));
+        for stmt in stmts {
+            stmt.visit_with(self);
+        }
+    }
+}
+
+impl Visit for FreeVariables {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Ident(i) => self.reference(&i.sym),
+            _ => expr.visit_children_with(self),
+        }
+    }
+
+    fn visit_simple_assign_target(&mut self, target: &SimpleAssignTarget) {
+        match target {
+            SimpleAssignTarget::Ident(i) => self.reference(&i.sym),
+            _ => target.visit_children_with(self),
+        }
+    }
+
+    // identifiers in assignment patterns (`[a, b] = x`, `for (a of x)`),
+    // declaration patterns are handled by visit_pat_references
+    fn visit_pat(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(i) => self.reference(&i.sym),
+            _ => pat.visit_children_with(self),
+        }
+    }
+
+    fn visit_object_pat_prop(&mut self, prop: &ObjectPatProp) {
+        match prop {
+            ObjectPatProp::Assign(a) => {
+                self.reference(&a.key.sym);
+                a.value.visit_with(self);
+            }
+            _ => prop.visit_children_with(self),
+        }
+    }
+
+    fn visit_prop(&mut self, prop: &Prop) {
+        match prop {
+            Prop::Shorthand(i) => self.reference(&i.sym),
+            _ => prop.visit_children_with(self),
+        }
+    }
+
+    fn visit_jsx_element_name(&mut self, name: &JSXElementName) {
+        match name {
+            // lowercase names are intrinsic elements, not variables
+            JSXElementName::Ident(i) if i.sym.starts_with(|c: char| c.is_ascii_uppercase()) => {
+                self.reference(&i.sym)
+            }
+            JSXElementName::JSXMemberExpr(m) => m.visit_with(self),
+            _ => {}
+        }
+    }
+
+    fn visit_jsx_object(&mut self, object: &JSXObject) {
+        match object {
+            JSXObject::Ident(i) => self.reference(&i.sym),
+            JSXObject::JSXMemberExpr(m) => m.visit_with(self),
+        }
+    }
+
+    fn visit_var_declarator(&mut self, declarator: &VarDeclarator) {
+        // the bindings are declared when entering the enclosing scope
+        self.visit_pat_references(&declarator.name);
+        declarator.init.visit_with(self);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        function.decorators.visit_with(self);
+        self.push_scope([Atom::from("arguments")]);
+        self.visit_params(function.params.iter().map(|p| &p.pat));
+        for param in &function.params {
+            param.decorators.visit_with(self);
+        }
+        if let Some(body) = &function.body {
+            self.visit_function_body(&body.stmts);
+        }
+        self.pop_scope();
+    }
+
+    fn visit_param(&mut self, param: &Param) {
+        // only reached for constructors, other params are handled in visit_function
+        param.decorators.visit_with(self);
+        self.visit_params([&param.pat]);
+    }
+
+    fn visit_ts_param_prop(&mut self, param: &TsParamProp) {
+        param.decorators.visit_with(self);
+        match &param.param {
+            TsParamPropParam::Ident(i) => self.declare([i.sym.clone()]),
+            TsParamPropParam::Assign(a) => {
+                let mut names = vec![];
+                pat_bindings(&a.left, &mut names);
+                self.declare(names);
+                a.right.visit_with(self);
+            }
+        }
+    }
+
+    fn visit_constructor(&mut self, constructor: &Constructor) {
+        constructor.key.visit_with(self);
+        self.push_scope([Atom::from("arguments")]);
+        constructor.params.visit_with(self);
+        if let Some(body) = &constructor.body {
+            self.visit_function_body(&body.stmts);
+        }
+        self.pop_scope();
+    }
+
+    fn visit_setter_prop(&mut self, setter: &SetterProp) {
+        setter.key.visit_with(self);
+        self.push_scope([Atom::from("arguments")]);
+        self.visit_params([&*setter.param]);
+        if let Some(body) = &setter.body {
+            self.visit_function_body(&body.stmts);
+        }
+        self.pop_scope();
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        self.push_scope([]);
+        self.visit_params(arrow.params.iter());
+        match &*arrow.body {
+            BlockStmtOrExpr::BlockStmt(b) => self.visit_function_body(&b.stmts),
+            BlockStmtOrExpr::Expr(e) => e.visit_with(self),
+        }
+        self.pop_scope();
+    }
+
+    fn visit_fn_decl(&mut self, fn_decl: &FnDecl) {
+        // the name is declared in the enclosing scope
+        fn_decl.function.visit_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, fn_expr: &FnExpr) {
+        self.push_scope(fn_expr.ident.iter().map(|i| i.sym.clone()));
+        fn_expr.function.visit_with(self);
+        self.pop_scope();
+    }
+
+    fn visit_class_decl(&mut self, class_decl: &ClassDecl) {
+        class_decl.class.visit_with(self);
+    }
+
+    fn visit_class_expr(&mut self, class_expr: &ClassExpr) {
+        self.push_scope(class_expr.ident.iter().map(|i| i.sym.clone()));
+        class_expr.class.visit_with(self);
+        self.pop_scope();
+    }
+
+    fn visit_block_stmt(&mut self, block: &BlockStmt) {
+        self.push_scope(lexical_declarations(&block.stmts));
+        block.stmts.visit_with(self);
+        self.pop_scope();
+    }
+
+    fn visit_for_stmt(&mut self, for_stmt: &ForStmt) {
+        self.push_scope([]);
+        if let Some(VarDeclOrExpr::VarDecl(v)) = &for_stmt.init {
+            if v.kind != VarDeclKind::Var {
+                let mut names = vec![];
+                for d in &v.decls {
+                    pat_bindings(&d.name, &mut names);
+                }
+                self.declare(names);
+            }
+        }
+        for_stmt.visit_children_with(self);
+        self.pop_scope();
+    }
+
+    fn visit_for_in_stmt(&mut self, for_in: &ForInStmt) {
+        self.push_scope(for_head_declarations(&for_in.left));
+        for_in.visit_children_with(self);
+        self.pop_scope();
+    }
+
+    fn visit_for_of_stmt(&mut self, for_of: &ForOfStmt) {
+        self.push_scope(for_head_declarations(&for_of.left));
+        for_of.visit_children_with(self);
+        self.pop_scope();
+    }
+
+    fn visit_catch_clause(&mut self, catch: &CatchClause) {
+        let mut names = vec![];
+        if let Some(param) = &catch.param {
+            pat_bindings(param, &mut names);
+        }
+        self.push_scope(names);
+        if let Some(param) = &catch.param {
+            self.visit_pat_references(param);
+        }
+        catch.body.visit_with(self);
+        self.pop_scope();
+    }
+
+    // labels are not variables
+    fn visit_labeled_stmt(&mut self, labeled: &LabeledStmt) {
+        labeled.body.visit_with(self);
+    }
+    fn visit_break_stmt(&mut self, _: &BreakStmt) {}
+    fn visit_continue_stmt(&mut self, _: &ContinueStmt) {}
+}
+
+/// Names declared by `let`/`const` in the head of a `for...in`/`for...of` loop
+pub fn for_head_declarations(head: &ForHead) -> Vec<Atom> {
+    let mut names = vec![];
+    if let ForHead::VarDecl(v) = head {
+        if v.kind != VarDeclKind::Var {
+            for d in &v.decls {
+                pat_bindings(&d.name, &mut names);
+            }
+        }
+    }
+    names
+}
@@ -0,0 +1,249 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use swc_common::{
+    comments::SingleThreadedComments,
+    errors::{DiagnosticBuilder, Emitter as DiagnosticEmitter, Handler, Level, HANDLER},
+    sync::Lrc,
+    FileName, Globals, SourceMap, Span, Spanned, GLOBALS, DUMMY_SP,
+};
+use swc_ecma_ast::{EsVersion, Program};
+use swc_ecma_codegen::{text_writer::JsWriter, Emitter};
+use swc_ecma_parser::{lexer::Lexer, EsSyntax, Parser, StringInput, Syntax, TsSyntax};
+use swc_ecma_visit::FoldWith;
+
+use crate::config::{JusixConfig, TransformMetadata};
+use crate::manifest::ReactivityManifest;
+use crate::pass::JusixPass;
+
+/// Source language of a module passed to [`transform`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceSyntax {
+    Ts,
+    Tsx,
+    Jsx,
+}
+
+impl SourceSyntax {
+    /// Guesses the syntax from the file extension, falling back to JSX
+    pub fn from_filename(filename: &str) -> Self {
+        match filename.rsplit('.').next() {
+            Some("ts" | "mts" | "cts") => SourceSyntax::Ts,
+            Some("tsx") => SourceSyntax::Tsx,
+            _ => SourceSyntax::Jsx,
+        }
+    }
+
+    fn to_parser_syntax(self) -> Syntax {
+        match self {
+            SourceSyntax::Ts | SourceSyntax::Tsx => Syntax::Typescript(TsSyntax {
+                tsx: self == SourceSyntax::Tsx,
+                decorators: true,
+                ..Default::default()
+            }),
+            SourceSyntax::Jsx => Syntax::Es(EsSyntax {
+                jsx: true,
+                decorators: true,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// Options for [`transform`]
+#[derive(Debug, Clone, Default)]
+pub struct TransformOptions {
+    /// Source syntax, inferred from the filename if not set
+    pub syntax: Option<SourceSyntax>,
+    /// Generate a source map for the output
+    pub source_map: bool,
+    /// Name of the environment (e.g. `development` or `production`)
+    pub env_name: Option<String>,
+    /// Generate the reactivity manifest of the module, see [`crate::manifest`]
+    pub manifest: bool,
+    /// Path of the module inside the `manifestDir`, relative imports are resolved against
+    /// it to find their manifests. Defaults to the filename.
+    pub module_path: Option<String>,
+    /// Plugin options, same as the SWC plugin config
+    pub config: JusixConfig,
+}
+
+/// Result of [`transform`]
+#[derive(Debug, Clone)]
+pub struct TransformOutput {
+    /// Transformed code. TypeScript and JSX syntax is kept as is, only the
+    /// reactivity transforms are applied.
+    pub code: String,
+    /// Source map as JSON, if [`TransformOptions::source_map`] was set
+    pub map: Option<String>,
+    /// Reactivity manifest as JSON, if [`TransformOptions::manifest`] was set
+    /// and the source is a module
+    pub manifest: Option<String>,
+    /// Warnings about code that could not be transformed and was left unchanged
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// A single error or warning that occurred while transforming a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub filename: String,
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column
+    pub column: usize,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.filename, self.line, self.column, self.message)
+    }
+}
+
+/// Errors that prevented a module from being transformed
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn push(&mut self, cm: &SourceMap, filename: &str, span: Span, message: String) {
+        let loc = cm.lookup_char_pos(span.lo);
+        self.0.push(Diagnostic {
+            message,
+            filename: filename.to_string(),
+            line: loc.line,
+            column: loc.col_display + 1,
+        });
+    }
+}
+
+/// Collects the diagnostics that the pass reports through the swc [`HANDLER`]
+#[derive(Clone, Default)]
+struct DiagnosticCollector(Arc<Mutex<Vec<(Level, String, Span)>>>);
+
+impl DiagnosticEmitter for DiagnosticCollector {
+    fn emit(&mut self, db: &DiagnosticBuilder<'_>) {
+        let span = db.span.primary_span().unwrap_or(DUMMY_SP);
+        self.0
+            .lock()
+            .expect("diagnostics lock is not poisoned")
+            .push((db.level, db.message(), span));
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, diagnostic) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+/// Parses `source`, applies the jusix transforms and generates code.
+///
+/// This is the native counterpart of the SWC plugin for hosts that embed jusix directly.
+pub fn transform(
+    source: &str,
+    filename: &str,
+    options: &TransformOptions,
+) -> Result<TransformOutput, Diagnostics> {
+    GLOBALS.set(&Globals::new(), || {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(
+            Lrc::new(FileName::Custom(filename.into())),
+            source.into(),
+        );
+        let comments = SingleThreadedComments::default();
+        let syntax = options
+            .syntax
+            .unwrap_or_else(|| SourceSyntax::from_filename(filename));
+
+        let lexer = Lexer::new(
+            syntax.to_parser_syntax(),
+            EsVersion::latest(),
+            StringInput::from(&*fm),
+            Some(&comments),
+        );
+        let mut parser = Parser::new_from(lexer);
+        let program = parser.parse_program();
+
+        let mut diagnostics = Diagnostics::default();
+        for e in parser.take_errors() {
+            diagnostics.push(&cm, filename, e.span(), e.kind().msg().into_owned());
+        }
+        let program = match program {
+            Ok(program) if diagnostics.0.is_empty() => program,
+            Ok(_) => return Err(diagnostics),
+            Err(e) => {
+                diagnostics.push(&cm, filename, e.span(), e.kind().msg().into_owned());
+                return Err(diagnostics);
+            }
+        };
+
+        let metadata = TransformMetadata {
+            filename: Some(filename.to_string()),
+            env_name: options.env_name.clone(),
+            module_path: options.module_path.clone(),
+        };
+        let mut pass = JusixPass::new(options.config.clone(), metadata);
+        let collector = DiagnosticCollector::default();
+        let handler = Handler::with_emitter(true, false, Box::new(collector.clone()));
+        let program: Program = HANDLER.set(&handler, || program.fold_with(&mut pass));
+
+        let mut warnings = Diagnostics::default();
+        for (level, message, span) in collector.0.lock().expect("diagnostics lock is not poisoned").drain(..) {
+            match level {
+                Level::Warning | Level::Note | Level::Help => warnings.push(&cm, filename, span, message),
+                _ => diagnostics.push(&cm, filename, span, message),
+            }
+        }
+        if !diagnostics.0.is_empty() {
+            return Err(diagnostics);
+        }
+        let manifest = options
+            .manifest
+            .then(|| pass.visitor().manifest().map(ReactivityManifest::to_json))
+            .flatten();
+
+        let mut code = vec![];
+        let mut mappings = vec![];
+        {
+            let mut emitter = Emitter {
+                cfg: swc_ecma_codegen::Config::default().with_target(EsVersion::latest()),
+                cm: cm.clone(),
+                comments: Some(&comments),
+                wr: JsWriter::new(
+                    cm.clone(),
+                    "\n",
+                    &mut code,
+                    options.source_map.then_some(&mut mappings),
+                ),
+            };
+            emitter
+                .emit_program(&program)
+                .expect("writing to a Vec<u8> cannot fail");
+        }
+
+        let map = options.source_map.then(|| {
+            let mut map = vec![];
+            cm.build_source_map(&mappings)
+                .to_writer(&mut map)
+                .expect("writing to a Vec<u8> cannot fail");
+            String::from_utf8(map).expect("source map is valid UTF-8")
+        });
+
+        Ok(TransformOutput {
+            code: String::from_utf8(code).expect("generated code is valid UTF-8"),
+            map,
+            manifest,
+            warnings: warnings.0,
+        })
+    })
+}
@@ -1,66 +1,370 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
 use swc_ecma_ast::{
     ArrowExpr, BlockStmt, BlockStmtOrExpr, CallExpr, Callee, Expr, ExprOrSpread, ExprStmt,
-    FnDecl, Ident, JSXEmptyExpr, JSXExpr, JSXExprContainer, Lit, Null, ReturnStmt, Stmt,
-    Str, VarDecl,
-    IdentName, JSXAttr, JSXAttrName, JSXAttrValue, JSXElement, JSXElementChild, JSXSpreadChild, MemberExpr, MemberProp
+    Ident, JSXExpr, JSXExprContainer, Lit, Module, Null, ReturnStmt, Stmt, Str,
+    IdentName, JSXAttr, JSXAttrName, JSXAttrValue, JSXElement, JSXElementChild, JSXSpreadChild, MemberExpr, MemberProp,
+    CatchClause, ClassExpr, Constructor, FnExpr, ForInStmt, ForOfStmt, ForStmt, Function, ImportSpecifier,
+    ModuleDecl, ModuleExportName, ModuleItem, ParamOrTsParamProp, Script, SetterProp, TsParamPropParam,
+    VarDecl, VarDeclKind, VarDeclOrExpr, Decl, ExportDecl, ImportDecl, Pat, Prop, PropName, PropOrSpread,
+    ExportSpecifier, ImportNamedSpecifier, Bool, OptChainBase, OptChainExpr, BinaryOp, TaggedTpl, Tpl, JSXAttrOrSpread
 };
 
-use swc_ecma_visit::{Fold, Visit, FoldWith, VisitWith};
+use swc_ecma_visit::{Fold, FoldWith};
 use swc_atoms::Atom;
-use swc_common::{util::take::Take, SyntaxContext, DUMMY_SP};
+use swc_common::{util::take::Take, Span, Spanned, SyntaxContext, DUMMY_SP};
+
+use crate::config::{CollectionMethod, JusixConfig, TransformMetadata};
+use crate::constant;
+use crate::errors;
+use crate::imports::HelperImports;
+use crate::manifest::{self, ReactivityManifest};
+use crate::scope::{self, Collection, Scopes};
 
 
-struct VariableCollector {
-    variables: Vec<String>,
+/// UIX/DATEX helper that a callee resolves to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Helper {
+    Dollar,
+    Run,
+    Always,
+    Pointer,
+    // another function that creates a reactive value, see JusixConfig::reactive_functions
+    Reactive,
 }
 
-impl VariableCollector {
-    fn new() -> Self {
-        VariableCollector {
-            variables: Vec::new(),
+#[derive(Default)]
+pub struct TransformVisitor {
+    config: JusixConfig,
+    metadata: TransformMetadata,
+    // helper imports of the current module, if import injection is enabled
+    imports: Option<HelperImports>,
+    // module level bindings, with the helper they refer to if imported from a helper module
+    module_bindings: HashMap<Atom, Option<Helper>>,
+    // module level bindings that are never reactive (e.g. `const LABEL = "Total"`)
+    constant_bindings: HashSet<Atom>,
+    // module level bindings that hold reactive values, only collected in precise mode
+    reactive_bindings: HashSet<Atom>,
+    // bindings that always hold an array, Map or Set, see scope::collection_bindings
+    collection_bindings: HashMap<Atom, Collection>,
+    // declarations in the function and block scopes around the current node
+    scopes: Scopes,
+    // exports of the last transformed module
+    manifest: Option<ReactivityManifest>,
+}
+
+impl TransformVisitor {
+    pub fn new(config: JusixConfig, metadata: TransformMetadata) -> Self {
+        TransformVisitor {
+            config,
+            metadata,
+            imports: None,
+            module_bindings: HashMap::new(),
+            constant_bindings: HashSet::new(),
+            reactive_bindings: HashSet::new(),
+            collection_bindings: HashMap::new(),
+            scopes: Scopes::default(),
+            manifest: None,
         }
     }
-}
 
-impl Visit for VariableCollector {
-    fn visit_ident(&mut self, ident: &Ident) {
-        // add variable to list if not already present
-        if !self.variables.contains(&ident.sym.to_string()) {
-            self.variables.push(ident.sym.to_string());
+    pub fn config(&self) -> &JusixConfig {
+        &self.config
+    }
+
+    pub fn metadata(&self) -> &TransformMetadata {
+        &self.metadata
+    }
+
+    // creates an identifier for an emitted runtime helper, using the imported local name if needed
+    fn helper_ident(&mut self, helper: String, ctxt: SyntaxContext) -> Ident {
+        let local = match &mut self.imports {
+            Some(imports) => imports.use_helper(&helper),
+            None => helper,
+        };
+        Ident::new(local.into(), DUMMY_SP, ctxt)
+    }
+
+    // helpers that the transform can emit, and that are imported from the import source
+    fn emitted_helpers(&self) -> [&str; 6] {
+        let helpers = &self.config.helpers;
+        [&helpers.always, &helpers.pointer, &helpers.prop, &helpers.if_, &helpers.template, &helpers.iterate]
+            .map(String::as_str)
+    }
+
+    fn helper_by_name(&self, name: &str) -> Option<Helper> {
+        let helpers = &self.config.helpers;
+        [
+            (&helpers.dollar, Helper::Dollar),
+            (&helpers.run, Helper::Run),
+            (&helpers.always, Helper::Always),
+            (&helpers.pointer, Helper::Pointer),
+            // emitted for conditionals, templates and iteration, create reactive values like _$()
+            (&helpers.if_, Helper::Always),
+            (&helpers.template, Helper::Always),
+            (&helpers.iterate, Helper::Always),
+            (&helpers.prop, Helper::Reactive),
+        ]
+        .into_iter()
+        .find(|(helper_name, _)| **helper_name == name)
+        .map(|(_, helper)| helper)
+        .or_else(|| {
+            self.config
+                .reactive_functions
+                .iter()
+                .any(|f| f == name)
+                .then_some(Helper::Reactive)
+        })
+    }
+
+    // returns the helper that a callee refers to: either imported from a helper module,
+    // or an unshadowed global with the name of a helper
+    fn resolve_helper(&self, callee: &Expr) -> Option<Helper> {
+        let Expr::Ident(ident) = callee.unwrap_parens() else {
+            return None;
+        };
+        if self.scopes.is_declared(&ident.sym) {
+            return None;
+        }
+        match self.module_bindings.get(&ident.sym) {
+            Some(binding) => *binding,
+            None => self.helper_by_name(&ident.sym),
         }
     }
 
-    fn visit_var_decl(&mut self, var_decl: &VarDecl) {
-        for decl in &var_decl.decls {
-            decl.name.visit_with(self);
+    fn callee_is_helper(&self, callee: &Callee, helpers: &[Helper]) -> bool {
+        match callee {
+            Callee::Expr(e) => self.resolve_helper(e).is_some_and(|h| helpers.contains(&h)),
+            _ => false,
         }
     }
 
-    fn visit_fn_decl(&mut self, fn_decl: &FnDecl) {
-        // Visit the function body
-        fn_decl.function.body.visit_with(self);
+    // collects the module level bindings and resolves imports from helper modules
+    fn collect_module_bindings(&mut self, module: &Module) {
+        self.module_bindings = scope::module_declarations(module)
+            .into_iter()
+            .map(|name| (name, None))
+            .collect();
+
+        self.constant_bindings = HashSet::new();
+        for item in &module.body {
+            match item {
+                ModuleItem::Stmt(Stmt::Decl(Decl::Var(var)))
+                | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl: Decl::Var(var), .. }))
+                    if var.kind == VarDeclKind::Const =>
+                {
+                    // declarations are collected in order, so constants can be built from earlier constants
+                    for declarator in &var.decls {
+                        if let (Pat::Ident(name), Some(init)) = (&declarator.name, &declarator.init) {
+                            if self.is_constant(init) {
+                                self.constant_bindings.insert(name.sym.clone());
+                            }
+                        }
+                    }
+                }
+                // JSON modules only contain static data
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import)) if is_json_import(import) => {
+                    self.constant_bindings
+                        .extend(import.specifiers.iter().map(|s| s.local().sym.clone()));
+                }
+                _ => {}
+            }
+        }
+
+        for item in &module.body {
+            let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item else {
+                continue;
+            };
+            if import.type_only || !self.config.is_helper_module(&import.src.value) {
+                continue;
+            }
+            for specifier in &import.specifiers {
+                if let ImportSpecifier::Named(named) = specifier {
+                    let helper = self.helper_by_name(imported_name(named));
+                    self.module_bindings.insert(named.local.sym.clone(), helper);
+                }
+            }
+        }
+
+        self.collection_bindings = scope::collection_bindings(module, &|callee| {
+            matches!(self.resolve_helper(callee), Some(Helper::Dollar | Helper::Pointer))
+        });
+
+        // also needed without precise mode, for the manifest of this module
+        self.reactive_bindings = HashSet::new();
+        self.collect_module_reactive_bindings(module);
     }
 
-    fn visit_block_stmt(&mut self, block_stmt: &BlockStmt) {
-        for stmt in &block_stmt.stmts {
-            stmt.visit_with(self);
+    // collects the module level bindings that can hold reactive values
+    fn collect_module_reactive_bindings(&mut self, module: &Module) {
+        let mut vars = vec![];
+        for item in &module.body {
+            match item {
+                ModuleItem::Stmt(Stmt::Decl(Decl::Var(var)))
+                | ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl { decl: Decl::Var(var), .. })) => {
+                    vars.push(&**var)
+                }
+                // values imported from other modules might be reactive, unless their manifest
+                // says otherwise, except for helpers and static JSON data
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import))
+                    if !import.type_only && !is_json_import(import) =>
+                {
+                    let manifest = self.read_manifest(&import.src.value);
+                    for specifier in &import.specifiers {
+                        let local = &specifier.local().sym;
+                        let is_helper = self.module_bindings.get(local).is_some_and(Option::is_some);
+                        let is_reactive = match specifier {
+                            ImportSpecifier::Named(named) if named.is_type_only => false,
+                            ImportSpecifier::Named(named) => {
+                                is_reactive_export(manifest.as_ref(), imported_name(named))
+                            }
+                            ImportSpecifier::Default(_) => is_reactive_export(manifest.as_ref(), "default"),
+                            ImportSpecifier::Namespace(_) => has_reactive_exports(manifest.as_ref()),
+                        };
+                        if is_reactive && !is_helper {
+                            self.reactive_bindings.insert(local.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
         }
+        let reactive = self.reactive_declarations(vars);
+        self.reactive_bindings.extend(reactive);
+    }
+
+    // returns the variables declared with a reactive initializer,
+    // e.g. `const x = $(0)`, `const y = always(() => x * 2)` or `const z = x`
+    fn reactive_declarations<'a>(&self, vars: impl IntoIterator<Item = &'a VarDecl>) -> Vec<Atom> {
+        let mut reactive: Vec<Atom> = vec![];
+        for declarator in vars.into_iter().flat_map(|var| &var.decls) {
+            let (Pat::Ident(name), Some(init)) = (&declarator.name, &declarator.init) else {
+                continue;
+            };
+            if self.is_reactive_init(init, &reactive) {
+                reactive.push(name.sym.clone());
+            }
+        }
+        reactive
+    }
+
+    // returns true if the value of `init` is reactive, `reactive` are additional reactive bindings
+    fn is_reactive_init(&self, init: &Expr, reactive: &[Atom]) -> bool {
+        match init.unwrap_parens() {
+            Expr::Call(call) => self.callee_is_helper(
+                &call.callee,
+                &[Helper::Dollar, Helper::Pointer, Helper::Always, Helper::Reactive],
+            ),
+            // aliases of reactive values and their properties
+            Expr::Ident(_) | Expr::Member(_) => {
+                let mut root = init.unwrap_parens();
+                while let Expr::Member(m) = root {
+                    root = m.obj.unwrap_parens();
+                }
+                matches!(root, Expr::Ident(i) if reactive.contains(&i.sym) || self.is_reactive_binding(&i.sym))
+            }
+            _ => false,
+        }
+    }
+
+    // reads the manifest of an imported module from the manifest directory
+    fn read_manifest(&self, specifier: &str) -> Option<ReactivityManifest> {
+        let dir = self.config.manifest_dir.as_ref()?;
+        let importer = self.metadata.module_path.as_ref().or(self.metadata.filename.as_ref())?;
+        let module = manifest::resolve_import(importer, specifier)?;
+        ReactivityManifest::read(Path::new(dir), &module)
     }
-}
 
+    // lists the exports of the module and whether they are reactive
+    fn build_manifest(&self, module: &Module) -> ReactivityManifest {
+        let mut exports = BTreeMap::new();
+        for item in &module.body {
+            let ModuleItem::ModuleDecl(decl) = item else {
+                continue;
+            };
+            match decl {
+                ModuleDecl::ExportDecl(export) => {
+                    let mut names = vec![];
+                    scope::decl_bindings(&export.decl, &mut names);
+                    for name in names {
+                        let reactive = self.reactive_bindings.contains(&name);
+                        exports.insert(name.to_string(), reactive);
+                    }
+                }
+                ModuleDecl::ExportNamed(named) if !named.type_only => {
+                    // re-exports are reactive if the manifest of their module says so
+                    let manifest = named.src.as_ref().and_then(|src| self.read_manifest(&src.value));
+                    for specifier in &named.specifiers {
+                        let (exported, reactive) = match specifier {
+                            ExportSpecifier::Named(n) if n.is_type_only => continue,
+                            ExportSpecifier::Named(n) => {
+                                let orig = export_name(&n.orig);
+                                let exported = n.exported.as_ref().map_or(orig, export_name);
+                                let reactive = match named.src {
+                                    Some(_) => is_reactive_export(manifest.as_ref(), orig),
+                                    None => self.reactive_bindings.contains(orig),
+                                };
+                                (exported, reactive)
+                            }
+                            ExportSpecifier::Namespace(n) => (
+                                export_name(&n.name),
+                                has_reactive_exports(manifest.as_ref()),
+                            ),
+                            ExportSpecifier::Default(d) => {
+                                (&d.exported.sym, is_reactive_export(manifest.as_ref(), "default"))
+                            }
+                        };
+                        exports.insert(exported.to_string(), reactive);
+                    }
+                }
+                ModuleDecl::ExportDefaultExpr(export) => {
+                    exports.insert("default".into(), self.is_reactive_init(&export.expr, &[]));
+                }
+                ModuleDecl::ExportDefaultDecl(_) => {
+                    exports.insert("default".into(), false);
+                }
+                _ => {}
+            }
+        }
+        ReactivityManifest { exports }
+    }
 
-const DOLLAR_METHODS: [&'static str; 3] = [
-    "map",
-    "filter",
-    "reduce",
-];
+    /// Reactivity manifest of the last transformed module, `None` for scripts
+    pub fn manifest(&self) -> Option<&ReactivityManifest> {
+        self.manifest.as_ref()
+    }
 
+    fn is_reactive_binding(&self, sym: &Atom) -> bool {
+        match self.scopes.is_reactive(sym) {
+            Some(reactive) => reactive,
+            None => self.reactive_bindings.contains(sym),
+        }
+    }
 
-pub struct TransformVisitor;
+    // returns true if the expression reads a reactive binding, or a property of `this`,
+    // which are usually reactive fields of class components
+    fn references_reactive(&self, expr: &Expr) -> bool {
+        scope::reads_this(expr)
+            || scope::free_variables(expr)
+                .iter()
+                .any(|v| self.is_reactive_binding(v))
+    }
+
+    // returns true if the expression can never change, see [`constant::is_constant_expr`]
+    fn is_constant(&self, expr: &Expr) -> bool {
+        constant::is_constant_expr(expr, &|ident| {
+            !self.scopes.is_declared(&ident.sym) && self.constant_bindings.contains(&ident.sym)
+        })
+    }
 
-impl TransformVisitor {
     // wraps in expression in always() if needed
     fn transform_expr_reactive(&mut self, e: Box<Expr>) -> Box<Expr> {
+        self.transform_expr_reactive_with(e, self.config.precise)
+    }
+
+    // in precise mode, expressions that don't read reactive bindings are kept as they are
+    fn transform_expr_reactive_with(&mut self, e: Box<Expr>, precise: bool) -> Box<Expr> {
         match e.unwrap_parens() {
             // keep single literal values
             Expr::Lit(_) | Expr::JSXElement(_) | Expr::Ident(_) => e,
@@ -68,169 +372,428 @@ impl TransformVisitor {
             // keep functions
             Expr::Arrow(_) | Expr::Fn(_) => e,
 
+            // `this` can't be reassigned, e.g. prop(this, 'count') in class components
+            Expr::This(_) => e,
+
+            // keep constant expressions, they never change
+            expr if self.is_constant(expr) => e,
+
+            // precise mode: keep properties of non-reactive values
+            Expr::Member(_) if precise && !self.references_reactive(&e) => e,
+
             // has a $.x property, don't add always
-            Expr::Member(m)
-                if m.obj.is_member()
-                    && (m.obj.as_member().unwrap().prop.is_ident_with("$")
-                        || m.obj.as_member().unwrap().prop.is_ident_with("$$")) =>
-            {
-                e
+            Expr::Member(MemberExpr { obj, .. }) if is_dollar_member(obj) && self.collection_method(&e).is_none() => e,
+
+            // convert items.filter(f).map(g) to items.$.filter(f).$.map(g)
+            // and Object.entries(x).map(f) to _$iter(x, 'entries').$.map(f)
+            expr if self.collection_method(expr).is_some() || self.iteration_source(expr).is_some() => {
+                let mut root = expr;
+                while let Some((member, _)) = self.collection_method(root) {
+                    root = member.obj.unwrap_parens();
+                }
+                let iterated = match self.iteration_source(root) {
+                    Some((collection, ..)) => {
+                        root = collection;
+                        true
+                    }
+                    None => false,
+                };
+                // in precise mode, only use the reactive methods if the collection can be reactive
+                let reactive = !precise || self.references_reactive(root);
+                if iterated || is_collection_receiver(root) {
+                    self.transform_collection_chain(expr, reactive)
+                } else {
+                    // e.g. [...items].map(f) or name.split(",").map(f), there is no reactive collection to call $ on
+                    let chain = self.transform_collection_chain(expr, false);
+                    if reactive { self.wrap_always(chain) } else { chain }
+                }
             }
 
             // x.y property, convert to prop(x, 'y')
-            Expr::Member(m) =>
-                    Box::new(Expr::Call(
-                        CallExpr {
-                            span: DUMMY_SP,
-                            callee: Callee::Expr(Box::new(Expr::Ident(Ident::new(
-                                "prop".into(),
-                                DUMMY_SP,
-                                Default::default(),
-                            )))),
-                            args: vec![
-                                ExprOrSpread {
-                                    expr: self.transform_expr_reactive(m.obj.clone()),
-                                    spread: None
-                                },
-                                // convert prop to string
-                                match &m.prop {
-                                    MemberProp::Ident(_) => Expr::Lit(Lit::Str(Str {
-                                        span: DUMMY_SP,
-                                        value: m.prop.as_ident().unwrap().sym.clone().into(),
-                                        raw: None
-                                    })).into(),
-                                    MemberProp::Computed(e) => e.expr.clone().into(),
-                                    MemberProp::PrivateName(_) => panic!("Private name not supported"),
-                                }
-                            ],
-                            type_args: Take::dummy(),
-                            ctxt: Default::default(),
-                        }
-                )
-            ),
-
-            // convert array.map(() => {}) to array.$.map(() => {})
-            // TODO
-            Expr::Call(c)
-                if c.callee.is_expr()
-                    && c.callee.as_expr().unwrap().is_member()
-                    // any DOLLAR_METHODS
-                    && DOLLAR_METHODS.iter().any(|m| c.callee.as_expr().unwrap().as_member().unwrap().prop.is_ident_with(m)) =>
-                    // && (c.callee.as_expr().unwrap().as_member().unwrap().prop.is_ident_with("map")) =>
-            {
-                let member = c.callee.as_expr().unwrap().as_member().unwrap();
-                let obj = member.obj.clone();
-                let prop = member.prop.clone();
+            Expr::Member(m) => match self.transform_member(m, false, precise) {
+                Some(prop) => prop,
+                None => self.wrap_always(e),
+            },
 
-                Box::new(Expr::Call(CallExpr {
-                    span: c.span,
-                    callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
-                        span: DUMMY_SP,
-                        obj: obj,
-                        prop: MemberProp::Ident(IdentName::from(
-                            format!("$.{}", prop.as_ident().unwrap().sym).to_string()
-                        )),
-                    }))),
-                    // transform first arg if it's a function, keep others
-                    args: c.args.clone().into_iter().map(|a| {
-                        match a {
-                            ExprOrSpread {
-                                expr: e,
-                                spread: None,
-                            } => match e.unwrap_parens() {
-                                Expr::Arrow(a1) => ExprOrSpread {
-                                    expr: {
-                                        let mut a2 = a1.clone();
-                                        a2.body = Box::new(
-                                           *a2.body.fold_with(self)
-                                        );
-                                        Box::new(Expr::Arrow(a2))
-                                    },
-                                    spread: None,
-                                },
-                                _ => ExprOrSpread {
-                                    expr: e,
-                                    spread: None,
-                                }
-                            },
-                            _ => a
-                        }
-                    }).collect(),
-                    type_args: Take::dummy(),
-                    ctxt: Default::default(),
-                }))
+            // x?.y optional chain, convert to null-safe prop(x, 'y', true).
+            // Links without ?. (the .z in x?.y.z) are only skipped if an earlier link short-circuits
+            Expr::OptChain(OptChainExpr { base, optional, .. }) if matches!(&**base, OptChainBase::Member(_)) => {
+                if precise && !self.references_reactive(&e) {
+                    return e;
+                }
+                let OptChainBase::Member(m) = &**base else {
+                    return e;
+                };
+                match self.transform_member(m, *optional, precise) {
+                    Some(prop) => prop,
+                    None => self.wrap_always(e),
+                }
             }
 
-
-            // already has an always() or $$() wrapper
-            Expr::Call(c)
-                if c.callee.is_expr()
-                    && (c.callee.as_expr().unwrap().is_ident_ref_to("_$")
-                        || c.callee.as_expr().unwrap().is_ident_ref_to("$$")) =>
+            // already has an always() or $$() wrapper, or is another reactive value like effect()
+            Expr::Call(c) if self.callee_is_helper(&c.callee, &[Helper::Always, Helper::Pointer, Helper::Reactive]) =>
             {
                 e
             }
 
             // convert redundant $()
-            Expr::Call(c)
-                if c.callee.is_expr() && (c.callee.as_expr().unwrap().is_ident_ref_to("$")) =>
+            Expr::Call(c) if self.callee_is_helper(&c.callee, &[Helper::Dollar]) =>
             {
                 Box::new(Expr::Call(self.fold_call_expr(c.clone())))
             }
 
-            // default: wrap in always
-            _ => Box::new(Expr::Call(CallExpr {
-                span: DUMMY_SP,
-                callee: Callee::Expr(Box::new(Expr::Ident(Ident::new(
-                    "_$".into(),
-                    DUMMY_SP,
-                    Default::default(),
-                )))),
-                args: vec![Expr::Arrow(ArrowExpr {
-                    span: DUMMY_SP,
-                    params: Take::dummy(),
-                    body: Box::new(BlockStmtOrExpr::Expr(e)),
-                    is_async: false,
-                    is_generator: false,
-                    type_params: Take::dummy(),
-                    return_type: Take::dummy(),
+            // already a reactive template
+            Expr::TaggedTpl(t) if self.resolve_helper(&t.tag) == Some(Helper::Always) => e,
+
+            // precise mode: keep expressions that don't read reactive values
+            _ if precise && !self.references_reactive(&e) => e,
+
+            // `a ${x.y}` template, convert to _$tpl`a ${prop(x, 'y')}`
+            Expr::Tpl(tpl) => {
+                let exprs = tpl.exprs
+                    .iter()
+                    .map(|expr| self.transform_expr_reactive_with(expr.clone(), precise))
+                    .collect();
+                Box::new(Expr::TaggedTpl(TaggedTpl {
+                    span: tpl.span,
                     ctxt: Default::default(),
-                })
-                .into()],
-                type_args: Take::dummy(),
-                ctxt: Default::default(),
-            })),
+                    tag: Box::new(Expr::Ident(self.helper_ident(self.config.helpers.template.clone(), Default::default()))),
+                    type_params: None,
+                    tpl: Box::new(Tpl {
+                        span: tpl.span,
+                        exprs,
+                        quasis: tpl.quasis.clone(),
+                    }),
+                }))
+            }
+
+            // default: wrap in always
+            _ => self.wrap_always(e),
         }
     }
 
-    fn transform_transferable_closure(arrow: &ArrowExpr, ctxt: SyntaxContext) -> ArrowExpr {
-        // find all variables used in the arrow function body
-        let mut collector = VariableCollector::new();
-        arrow.body.visit_with(&mut collector);
+    // returns the member and the table entry of collection method calls and properties,
+    // e.g. `items.map(...)` or `items.length`
+    fn collection_method<'a>(&self, expr: &'a Expr) -> Option<(&'a MemberExpr, &CollectionMethod)> {
+        let (member, property) = match expr {
+            Expr::Call(CallExpr { callee: Callee::Expr(callee), .. }) => match &**callee {
+                Expr::Member(member) => (member, false),
+                _ => return None,
+            },
+            Expr::Member(member) => (member, true),
+            _ => return None,
+        };
+        let MemberProp::Ident(name) = &member.prop else {
+            return None;
+        };
+        self.config
+            .collection_methods
+            .get(&*name.sym)
+            .filter(|method| method.property == property)
+            .filter(|method| {
+                !method.collection_only
+                    || matches!(member.obj.unwrap_parens(), Expr::Ident(i) if self.collection_bindings.contains_key(&i.sym))
+            })
+            .map(|method| (member, method))
+    }
+
+    // converts the collection methods of a chain to their `$` variant if `reactive` is set,
+    // and transforms the callbacks
+    fn transform_collection_chain(&mut self, expr: &Expr, reactive: bool) -> Box<Expr> {
+        let Some((member, method)) = self.collection_method(expr) else {
+            return match self.iteration_source(expr) {
+                Some((collection, kind, map_fn)) if reactive => {
+                    self.transform_iteration(collection.clone(), kind, map_fn.cloned(), expr.span())
+                }
+                _ => Box::new(expr.clone()),
+            };
+        };
+        let callbacks = method.callbacks.clone();
+        let member = MemberExpr {
+            span: member.span,
+            obj: if is_dollar_member(&member.obj) {
+                // already reactive, e.g. `items.$.map(...)`
+                member.obj.clone()
+            } else if reactive {
+                let obj = self.transform_collection_chain(member.obj.unwrap_parens(), reactive);
+                Box::new(Expr::Member(MemberExpr {
+                    span: obj.span(),
+                    obj,
+                    prop: MemberProp::Ident(IdentName::new("$".into(), DUMMY_SP)),
+                }))
+            } else {
+                self.transform_collection_chain(member.obj.unwrap_parens(), reactive)
+            },
+            prop: member.prop.clone(),
+        };
+
+        let Expr::Call(call) = expr else {
+            return Box::new(Expr::Member(member));
+        };
+        // the runtime can only reorder the items of reactive methods that render elements,
+        // the key extractor is passed right after the callback: `items.$.map(fn, keyFn)`
+        let key_fn = match call.args.as_slice() {
+            [ExprOrSpread { expr, spread: None }]
+                if is_dollar_member(&member.obj)
+                    && callbacks == [0]
+                    && KEYED_METHODS.iter().any(|m| member.prop.is_ident_with(m)) =>
+            {
+                match expr.unwrap_parens() {
+                    Expr::Arrow(arrow) => key_extractor(arrow, &member.prop),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let mut args: Vec<ExprOrSpread> = call.args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| match arg {
+                ExprOrSpread { expr, spread: None }
+                    if callbacks.contains(&i) && matches!(expr.unwrap_parens(), Expr::Arrow(_) | Expr::Fn(_)) =>
+                {
+                    ExprOrSpread {
+                        expr: expr.clone().fold_with(self),
+                        spread: None,
+                    }
+                }
+                _ => arg.clone(),
+            })
+            .collect();
+        if let Some(key_fn) = key_fn {
+            args.insert(1, Expr::Arrow(key_fn).into());
+        }
+        Box::new(Expr::Call(CallExpr {
+            span: call.span,
+            callee: Callee::Expr(Box::new(Expr::Member(member))),
+            args,
+            type_args: call.type_args.clone(),
+            ctxt: call.ctxt,
+        }))
+    }
+
+    // returns the iterated collection, the iteration kind and the map function of
+    // `Object.keys/values/entries(x)`, `Array.from(x, fn)` and `x.keys/values/entries()` on a Map or Set
+    fn iteration_source<'a>(&self, expr: &'a Expr) -> Option<(&'a Expr, Option<&'static str>, Option<&'a Expr>)> {
+        let Expr::Call(CallExpr { callee: Callee::Expr(callee), args, .. }) = expr else {
+            return None;
+        };
+        let Expr::Member(MemberExpr { obj, prop: MemberProp::Ident(method), .. }) = &**callee else {
+            return None;
+        };
+        let kind = match &*method.sym {
+            "keys" => Some("keys"),
+            "values" => Some("values"),
+            "entries" => Some("entries"),
+            "from" => None,
+            _ => return None,
+        };
+        if args.iter().any(|arg| arg.spread.is_some()) {
+            return None;
+        }
+        let Expr::Ident(receiver) = obj.unwrap_parens() else {
+            return None;
+        };
+        let is_global = |name: &str| {
+            &*receiver.sym == name
+                && !self.scopes.is_declared(&receiver.sym)
+                && !self.module_bindings.contains_key(&receiver.sym)
+        };
+        match (kind, args.as_slice()) {
+            // Object.entries(x)
+            (Some(_), [collection]) if is_global("Object") => Some((&*collection.expr, kind, None)),
+            // Array.from(x), Array.from(x, fn)
+            (None, [collection]) if is_global("Array") => Some((&*collection.expr, None, None)),
+            (None, [collection, map_fn]) if is_global("Array") => {
+                Some((&*collection.expr, None, Some(&*map_fn.expr)))
+            }
+            // map.entries()
+            (Some(_), []) if matches!(self.collection_bindings.get(&receiver.sym), Some(Collection::Map | Collection::Set)) => {
+                Some((&**obj, kind, None))
+            }
+            _ => None,
+        }
+    }
+
+    // converts to _$iter(collection, kind), or _$iter(collection).$.map(fn) for Array.from(collection, fn)
+    fn transform_iteration(&mut self, collection: Expr, kind: Option<&str>, map_fn: Option<Expr>, span: Span) -> Box<Expr> {
+        let mut args = vec![ExprOrSpread::from(Box::new(collection))];
+        if let Some(kind) = kind {
+            args.push(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: kind.into(), raw: None })).into());
+        }
+        let iteration = Expr::Call(CallExpr {
+            span,
+            callee: Callee::Expr(Box::new(Expr::Ident(self.helper_ident(self.config.helpers.iterate.clone(), Default::default())))),
+            args,
+            type_args: Take::dummy(),
+            ctxt: Default::default(),
+        });
+        let Some(map_fn) = map_fn else {
+            return Box::new(iteration);
+        };
+        let map = Expr::Call(CallExpr {
+            span,
+            callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+                span,
+                obj: Box::new(iteration),
+                prop: MemberProp::Ident(IdentName::new("map".into(), DUMMY_SP)),
+            }))),
+            args: vec![Box::new(map_fn).into()],
+            type_args: Take::dummy(),
+            ctxt: Default::default(),
+        });
+        self.transform_collection_chain(&map, true)
+    }
+
+    // wraps in _$(() => e), the wrapper maps to the wrapped expression
+    fn wrap_always(&mut self, e: Box<Expr>) -> Box<Expr> {
+        Box::new(Expr::Call(CallExpr {
+            span: e.span(),
+            callee: Callee::Expr(Box::new(Expr::Ident(self.helper_ident(self.config.helpers.always.clone(), Default::default())))),
+            args: vec![closure(e)],
+            type_args: Take::dummy(),
+            ctxt: Default::default(),
+        }))
+    }
+
+    // converts conditionals in JSX children to _$if(), so that the branches are only
+    // recreated if the other branch is selected, not on every change
+    fn transform_child_reactive(&mut self, e: Box<Expr>) -> Box<Expr> {
+        let lowered = match e.unwrap_parens() {
+            expr if self.is_constant(expr) || (self.config.precise && !self.references_reactive(expr)) => None,
+            expr => self.transform_conditional(expr),
+        };
+        lowered.unwrap_or_else(|| self.transform_expr_reactive(e))
+    }
+
+    // cond ? a : b  -> _$if(() => cond, () => a, () => b)
+    // a && b        -> _$if(() => a, () => b, (v) => v)
+    // a || b        -> _$if(() => a, (v) => v, () => b)
+    // a ?? b        -> _$if(() => a, (v) => v, () => b, true)
+    // the test is only evaluated once, the runtime passes its value to the selected branch.
+    // With `true` as last argument, the first branch is selected if the value is not null or undefined
+    fn transform_conditional(&mut self, expr: &Expr) -> Option<Box<Expr>> {
+        let (test, cons, alt, nullish) = match expr {
+            Expr::Cond(c) => {
+                let cons = self.transform_branch(c.cons.clone());
+                let alt = self.transform_branch(c.alt.clone());
+                (c.test.clone(), closure(cons), closure(alt), false)
+            }
+            Expr::Bin(b) => match b.op {
+                BinaryOp::LogicalAnd => {
+                    let right = self.transform_branch(b.right.clone());
+                    (b.left.clone(), closure(right), test_value(), false)
+                }
+                BinaryOp::LogicalOr | BinaryOp::NullishCoalescing => {
+                    let right = self.transform_branch(b.right.clone());
+                    (b.left.clone(), test_value(), closure(right), b.op == BinaryOp::NullishCoalescing)
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let mut args = vec![closure(test), cons, alt];
+        if nullish {
+            args.push(Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: true })).into());
+        }
+        Some(Box::new(Expr::Call(CallExpr {
+            span: expr.span(),
+            callee: Callee::Expr(Box::new(Expr::Ident(self.helper_ident(self.config.helpers.if_.clone(), Default::default())))),
+            args,
+            type_args: Take::dummy(),
+            ctxt: Default::default(),
+        })))
+    }
+
+    // nested JSX and conditionals are transformed, other values are made reactive like children
+    fn transform_branch(&mut self, e: Box<Expr>) -> Box<Expr> {
+        match e.unwrap_parens() {
+            Expr::JSXElement(_) | Expr::JSXFragment(_) => e.fold_with(self),
+            expr => match self.transform_conditional(expr) {
+                Some(lowered) => lowered,
+                None => self.transform_expr_reactive(e),
+            },
+        }
+    }
+
+    // converts x.y to prop(x, 'y'), or to the null-safe prop(x, 'y', true) inside optional chains.
+    // Returns None for private names, which prop() can't access from outside of the class,
+    // and for computed keys that can change, e.g. x[index]
+    fn transform_member(&mut self, m: &MemberExpr, optional: bool, precise: bool) -> Option<Box<Expr>> {
+        // convert prop to string
+        let key: ExprOrSpread = match &m.prop {
+            MemberProp::Ident(i) => Expr::Lit(Lit::Str(Str {
+                span: i.span,
+                value: i.sym.clone(),
+                raw: None
+            })).into(),
+            // prop() reads the key only once, reactive keys need a wrapper that is re-evaluated
+            MemberProp::Computed(c)
+                if !self.is_constant(&c.expr) && (!precise || self.references_reactive(&c.expr)) =>
+            {
+                return None
+            }
+            MemberProp::Computed(c) => c.expr.clone().into(),
+            MemberProp::PrivateName(_) => return None,
+        };
+        let mut args = vec![
+            ExprOrSpread {
+                expr: self.transform_expr_reactive_with(m.obj.clone(), precise),
+                spread: None
+            },
+            key,
+        ];
+        // null-safe access for ?. links
+        if optional {
+            args.push(Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: true })).into());
+        }
+        Some(Box::new(Expr::Call(CallExpr {
+            span: m.span,
+            callee: Callee::Expr(Box::new(Expr::Ident(self.helper_ident(self.config.helpers.prop.clone(), Default::default())))),
+            args,
+            type_args: Take::dummy(),
+            ctxt: Default::default(),
+        })))
+    }
+
+    fn transform_transferable_closure(&self, arrow: &ArrowExpr, ctxt: SyntaxContext) -> ArrowExpr {
+        // find all variables from outer scopes that are used in the arrow function
+        let helpers = &self.config.helpers;
+        let captured: Vec<Atom> = scope::free_variables(arrow)
+            .into_iter()
+            .filter(|v| {
+                // local declarations shadow the helpers and globals
+                if self.scopes.is_declared(v) || self.module_bindings.contains_key(v) {
+                    return true;
+                }
+                // runtime helpers and globals are also available where the closure is executed
+                ![&helpers.use_, &helpers.dollar, &helpers.always, &helpers.pointer, &helpers.prop, &helpers.run, &helpers.if_, &helpers.template, &helpers.iterate]
+                    .iter()
+                    .any(|h| **h == **v)
+                    && !self.config.globals.iter().any(|g| **g == **v)
+            })
+            .collect();
 
         let mut body_vec = vec![];
 
         // add use();
-        if collector.variables.len() > 0 {
+        if !captured.is_empty() {
             body_vec.push(Stmt::Expr(ExprStmt {
                 span: DUMMY_SP,
                 expr: Box::new(Expr::Call(CallExpr {
                     span: DUMMY_SP,
                     callee: Callee::Expr(Box::new(Expr::Ident(Ident::new(
-                        "use".into(),
+                        self.config.helpers.use_.as_str().into(),
                         DUMMY_SP,
                         ctxt,
                     )))),
-                    args: collector
-                        .variables
+                    args: captured
                         .iter()
-                        // ignore "use" variable
-                        .filter(|v| !(*v == "use"))
                         .map(|v| {
                             Expr::Lit(Lit::Str(Str {
                                 span: DUMMY_SP,
-                                value: Atom::from(v.clone()),
-                                raw: Some(Atom::from(v.clone())),
+                                value: v.clone(),
+                                raw: Some(v.clone()),
                             }))
                         })
                         .map(|v| v.into())
@@ -251,7 +814,7 @@ impl TransformVisitor {
             BlockStmtOrExpr::Expr(e) => {
                 // return + orignal expr
                 body_vec.push(Stmt::Return(ReturnStmt {
-                    span: DUMMY_SP,
+                    span: e.span(),
                     arg: Some(Box::new(*e.clone())),
                 }));
             }
@@ -263,7 +826,7 @@ impl TransformVisitor {
             params: arrow.params.clone(),
             // add use(); followed by original body
             body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt {
-                span: DUMMY_SP,
+                span: arrow.body.span(),
                 ctxt: arrow.ctxt,
                 stmts: body_vec,
             })),
@@ -274,7 +837,7 @@ impl TransformVisitor {
         }
     }
 
-    fn transform_transferable_call_expr(call: &CallExpr) -> CallExpr {
+    fn transform_transferable_call_expr(&self, call: &CallExpr) -> CallExpr {
         let arg = TransformVisitor::get_first_arg(call);
 
         match arg.unwrap_parens() {
@@ -283,7 +846,7 @@ impl TransformVisitor {
                 span: call.span,
                 callee: call.callee.clone(),
                 args: vec![Box::new(Expr::Arrow(
-                    TransformVisitor::transform_transferable_closure(a, call.ctxt),
+                    self.transform_transferable_closure(a, call.ctxt),
                 ))
                 .into()],
                 type_args: call.type_args.clone(),
@@ -293,6 +856,14 @@ impl TransformVisitor {
         }
     }
 
+    // folds the children of a node inside a new scope that declares `names`
+    fn fold_in_scope<N: FoldWith<Self>>(&mut self, names: Vec<Atom>, node: N) -> N {
+        self.scopes.push(names);
+        let node = node.fold_children_with(self);
+        self.scopes.pop();
+        node
+    }
+
     fn get_first_arg(call: &CallExpr) -> Box<Expr> {
         call.clone()
             .args
@@ -307,22 +878,178 @@ impl TransformVisitor {
 }
 
 impl Fold for TransformVisitor {
+    fn fold_module(&mut self, module: Module) -> Module {
+        self.collect_module_bindings(&module);
+        self.manifest = Some(self.build_manifest(&module));
+
+        let Some(import_source) = self.config.import_source.clone() else {
+            return module.fold_children_with(self);
+        };
+
+        self.imports = Some(HelperImports::new(&module, &import_source, &self.emitted_helpers()));
+        let mut module = module.fold_children_with(self);
+        if let Some(imports) = self.imports.take() {
+            imports.inject(&mut module);
+        }
+        module
+    }
+
+    fn fold_stmts(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        // the scope of the statements was already entered, mark its reactive declarations
+        if self.config.precise {
+            let vars = stmts.iter().filter_map(|stmt| match stmt {
+                Stmt::Decl(Decl::Var(var)) => Some(&**var),
+                _ => None,
+            });
+            let reactive = self.reactive_declarations(vars);
+            self.scopes.mark_reactive(reactive);
+        }
+        stmts.fold_children_with(self)
+    }
+
+    fn fold_script(&mut self, script: Script) -> Script {
+        // top level declarations of scripts shadow the globals
+        let mut names = scope::var_declarations(&script.body);
+        names.extend(scope::lexical_declarations(&script.body));
+
+        let Some(import_source) = self.config.import_source.clone() else {
+            return self.fold_in_scope(names, script);
+        };
+        self.imports = Some(HelperImports::for_script(&script, &import_source, &self.emitted_helpers()));
+        let script = self.fold_in_scope(names, script);
+        // only modules can import the helpers, the output would fail at runtime
+        if let Some(imports) = self.imports.take() {
+            let missing: Vec<_> = imports.missing().map(|helper| format!("`{helper}`")).collect();
+            if !missing.is_empty() {
+                errors::error(
+                    script.span,
+                    &format!(
+                        "{} can't be imported from \"{}\" into a script, add an import or export to make the file a module",
+                        missing.join(", "),
+                        import_source
+                    ),
+                );
+            }
+        }
+        script
+    }
+
+    fn fold_function(&mut self, function: Function) -> Function {
+        let body = function.body.as_ref().map(|b| b.stmts.as_slice()).unwrap_or_default();
+        let names = scope::function_declarations(function.params.iter().map(|p| &p.pat), body);
+        self.fold_in_scope(names, function)
+    }
+
+    fn fold_arrow_expr(&mut self, arrow: ArrowExpr) -> ArrowExpr {
+        let body = match &*arrow.body {
+            BlockStmtOrExpr::BlockStmt(b) => b.stmts.as_slice(),
+            BlockStmtOrExpr::Expr(_) => &[],
+        };
+        let names = scope::function_declarations(arrow.params.iter(), body);
+        self.fold_in_scope(names, arrow)
+    }
+
+    fn fold_constructor(&mut self, constructor: Constructor) -> Constructor {
+        let mut names = vec![];
+        for param in &constructor.params {
+            match param {
+                ParamOrTsParamProp::Param(p) => scope::pat_bindings(&p.pat, &mut names),
+                ParamOrTsParamProp::TsParamProp(p) => match &p.param {
+                    TsParamPropParam::Ident(i) => names.push(i.sym.clone()),
+                    TsParamPropParam::Assign(a) => scope::pat_bindings(&a.left, &mut names),
+                },
+            }
+        }
+        let body = constructor.body.as_ref().map(|b| b.stmts.as_slice()).unwrap_or_default();
+        names.extend(scope::function_declarations([], body));
+        self.fold_in_scope(names, constructor)
+    }
+
+    fn fold_setter_prop(&mut self, setter: SetterProp) -> SetterProp {
+        let body = setter.body.as_ref().map(|b| b.stmts.as_slice()).unwrap_or_default();
+        let names = scope::function_declarations([&*setter.param], body);
+        self.fold_in_scope(names, setter)
+    }
+
+    fn fold_fn_expr(&mut self, fn_expr: FnExpr) -> FnExpr {
+        let names = fn_expr.ident.iter().map(|i| i.sym.clone()).collect();
+        self.fold_in_scope(names, fn_expr)
+    }
+
+    fn fold_class_expr(&mut self, class_expr: ClassExpr) -> ClassExpr {
+        let names = class_expr.ident.iter().map(|i| i.sym.clone()).collect();
+        self.fold_in_scope(names, class_expr)
+    }
+
+    fn fold_block_stmt(&mut self, block: BlockStmt) -> BlockStmt {
+        let names = scope::lexical_declarations(&block.stmts);
+        self.fold_in_scope(names, block)
+    }
+
+    fn fold_catch_clause(&mut self, catch: CatchClause) -> CatchClause {
+        let mut names = vec![];
+        if let Some(param) = &catch.param {
+            scope::pat_bindings(param, &mut names);
+        }
+        self.fold_in_scope(names, catch)
+    }
+
+    fn fold_for_stmt(&mut self, for_stmt: ForStmt) -> ForStmt {
+        let mut names = vec![];
+        if let Some(VarDeclOrExpr::VarDecl(v)) = &for_stmt.init {
+            if v.kind != VarDeclKind::Var {
+                for d in &v.decls {
+                    scope::pat_bindings(&d.name, &mut names);
+                }
+            }
+        }
+        self.fold_in_scope(names, for_stmt)
+    }
+
+    fn fold_for_in_stmt(&mut self, for_in: ForInStmt) -> ForInStmt {
+        let names = scope::for_head_declarations(&for_in.left);
+        self.fold_in_scope(names, for_in)
+    }
+
+    fn fold_for_of_stmt(&mut self, for_of: ForOfStmt) -> ForOfStmt {
+        let names = scope::for_head_declarations(&for_of.left);
+        self.fold_in_scope(names, for_of)
+    }
+
     fn fold_call_expr(&mut self, call: CallExpr) -> CallExpr {
-        return match &call.callee {
+        match &call.callee {
             Callee::Expr(e) => {
                 let arg = TransformVisitor::get_first_arg(&call);
 
-                return match e.unwrap_parens() {
-                    Expr::Ident(i) if i.sym.eq_ignore_ascii_case("$") => {
-                        return match arg.unwrap_parens() {
+                match self.resolve_helper(e) {
+                    Some(Helper::Dollar) => {
+                        // $(value) takes exactly one value, leave other calls unchanged
+                        let dollar = &self.config.helpers.dollar;
+                        match call.args.as_slice() {
+                            [ExprOrSpread { spread: None, .. }] => {}
+                            [] => {
+                                errors::error(call.span, &format!("{dollar}() expects a value to make reactive"));
+                                return call;
+                            }
+                            [ExprOrSpread { spread: Some(spread), .. }] => {
+                                errors::error(*spread, &format!("Spread arguments are not supported in {dollar}()"));
+                                return call;
+                            }
+                            [_, extra, ..] => {
+                                errors::warning(
+                                    extra.span(),
+                                    &format!("{dollar}() only takes one argument, the call is not transformed"),
+                                );
+                                return call;
+                            }
+                        }
+
+                        match arg.unwrap_parens() {
                             // $$ ()
-                            Expr::Lit(_) | Expr::JSXElement(_) | Expr::Ident(_) => CallExpr {
-                                span: DUMMY_SP,
-                                callee: Callee::Expr(Box::new(Expr::Ident(Ident::new(
-                                    "$$".into(),
-                                    DUMMY_SP,
-                                    call.ctxt,
-                                )))),
+                            expr if matches!(expr, Expr::JSXElement(_) | Expr::Ident(_))
+                                || self.is_constant(expr) => CallExpr {
+                                span: call.span,
+                                callee: Callee::Expr(Box::new(Expr::Ident(self.helper_ident(self.config.helpers.pointer.clone(), call.ctxt)))),
                                 args: vec![arg.fold_with(self).into()],
                                 type_args: Take::dummy(),
                                 ctxt: call.ctxt,
@@ -330,22 +1057,19 @@ impl Fold for TransformVisitor {
 
                             // default: wrap in always
                             _ => {
-                                let reactive = self.transform_expr_reactive(arg.clone());
+                                // $() is explicitly reactive, also in precise mode
+                                let reactive = self.transform_expr_reactive_with(arg.clone(), false);
                                 match reactive.unwrap_parens() {
                                     Expr::Call(c) => c.clone(),
                                     // transform_expr_reactive returns a CallExpr in all cases except for Expr::Arrow(_) | Expr::Fn
                                     _ => CallExpr {
-                                        span: DUMMY_SP,
-                                        callee: Callee::Expr(Box::new(Expr::Ident(Ident::new(
-                                            "_$".into(),
-                                            DUMMY_SP,
-                                            call.ctxt,
-                                        )))),
+                                        span: call.span,
+                                        callee: Callee::Expr(Box::new(Expr::Ident(self.helper_ident(self.config.helpers.always.clone(), call.ctxt)))),
                                         args: vec![
                                             match arg.unwrap_parens() {
                                                 Expr::Arrow(_) | Expr::Fn(_) => arg.into(),
                                                 _ => Expr::Arrow(ArrowExpr {
-                                                    span: DUMMY_SP,
+                                                    span: arg.span(),
                                                     params: Take::dummy(),
                                                     body: Box::new(BlockStmtOrExpr::Expr(arg)),
                                                     is_async: false,
@@ -362,19 +1086,19 @@ impl Fold for TransformVisitor {
                                     }
                                 }
                             }
-                        };
+                        }
                     }
 
-                    Expr::Ident(i) if i.sym.eq_ignore_ascii_case("run") => {
+                    Some(Helper::Run) => {
                         // add "use()" to run (()=>{})
-                        return TransformVisitor::transform_transferable_call_expr(&call);
+                        self.transform_transferable_call_expr(&call)
                     }
 
                     _ => call,
-                };
+                }
             }
             _ => call,
-        };
+        }
 
         // if n.callee.is_expr() && n.callee.expect_expr().expect_ident().sym.eq_ignore_ascii_case("$") {
         //         return CallExpr {
@@ -397,37 +1121,52 @@ impl Fold for TransformVisitor {
     }
 
     fn fold_jsx_attr(&mut self, node: JSXAttr) -> JSXAttr {
-        // if attribute ends with :frontend, transform_transferable_call_expr
+        // if attribute ends with :frontend (configurable), transform_transferable_call_expr
         match node.name.clone() {
             JSXAttrName::JSXNamespacedName(name)
-                if name.name.sym.eq_ignore_ascii_case("frontend") =>
+                if name.name.sym.eq_ignore_ascii_case(&self.config.frontend_namespace) =>
             {
                 match node.value.clone() {
                     Some(JSXAttrValue::JSXExprContainer(c)) => {
                         match c.expr.clone() {
                             JSXExpr::Expr(e) => match e.unwrap_parens() {
-                                Expr::Arrow(a) => return JSXAttr {
+                                Expr::Arrow(a) => JSXAttr {
                                     span: node.span,
                                     name: node.name.clone(),
                                     value: Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
-                                        span: DUMMY_SP,
+                                        span: c.span,
                                         expr: JSXExpr::Expr(Box::new(Expr::Arrow(
-                                            TransformVisitor::transform_transferable_closure(
-                                                &a, a.ctxt,
+                                            self.transform_transferable_closure(
+                                                a, a.ctxt,
                                             ),
                                         ))),
                                     })),
                                 },
-                                Expr::Call(c) => return JSXAttr {
+                                Expr::Call(call) => JSXAttr {
                                     span: node.span,
                                     name: node.name.clone(),
                                     value: Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
-                                        span: DUMMY_SP,
+                                        span: c.span,
                                         expr: JSXExpr::Expr(Box::new(Expr::Call(
-                                            TransformVisitor::transform_transferable_call_expr(&c),
+                                            self.transform_transferable_call_expr(call),
                                         ))),
                                     })),
                                 },
+                                // only arrow functions can be transferred, plain functions have their own `this`
+                                Expr::Fn(f) => {
+                                    errors::warning(
+                                        f.function.span,
+                                        "Transferable closures must be arrow functions, the function is not transformed",
+                                    );
+                                    node
+                                }
+                                Expr::JSXElement(_) | Expr::JSXFragment(_) => {
+                                    errors::error(
+                                        e.span(),
+                                        &format!("Invalid :{} value, expected an arrow function", self.config.frontend_namespace),
+                                    );
+                                    node
+                                }
                                 _ => JSXAttr {
                                     span: node.span,
                                     name: node.name.clone(),
@@ -436,13 +1175,38 @@ impl Fold for TransformVisitor {
                                     )),
                                 },
                             },
-                            _ => node,
+                            JSXExpr::JSXEmptyExpr(empty) => {
+                                errors::error(
+                                    empty.span,
+                                    &format!("Empty :{} value, expected an arrow function", self.config.frontend_namespace),
+                                );
+                                node
+                            }
                         }
                     }
-                    _ => node,
+                    Some(value) => {
+                        errors::error(
+                            value.span(),
+                            &format!("Invalid :{} value, expected an arrow function", self.config.frontend_namespace),
+                        );
+                        node
+                    }
+                    None => {
+                        errors::error(
+                            node.span,
+                            &format!("Missing :{} value, expected an arrow function", self.config.frontend_namespace),
+                        );
+                        node
+                    }
                 }
             }
             _ => match node.value.clone() {
+                // event handlers and refs must stay plain values
+                Some(JSXAttrValue::JSXExprContainer(_))
+                    if matches!(&node.name, JSXAttrName::Ident(name) if self.config.is_plain_attribute(&name.sym)) =>
+                {
+                    node
+                }
                 Some(JSXAttrValue::JSXExprContainer(c)) => JSXAttr {
                     span: node.span,
                     name: node.name.clone(),
@@ -457,18 +1221,24 @@ impl Fold for TransformVisitor {
 
     fn fold_jsx_element_child(&mut self, child: JSXElementChild) -> JSXElementChild {
         match child {
+            JSXElementChild::JSXExprContainer(JSXExprContainer { span, expr: JSXExpr::Expr(e) }) => {
+                JSXElementChild::JSXExprContainer(JSXExprContainer {
+                    span,
+                    expr: JSXExpr::Expr(self.transform_child_reactive(e)),
+                })
+            }
             JSXElementChild::JSXExprContainer(c) => JSXElementChild::JSXExprContainer(
                 self.fold_jsx_expr_container(c),
             ),
             JSXElementChild::JSXSpreadChild(c) => JSXElementChild::JSXSpreadChild(
                 JSXSpreadChild {
-                    span: DUMMY_SP,
+                    span: c.span,
                     expr: self.transform_expr_reactive(c.expr)
                 }
             ),
             JSXElementChild::JSXElement(e) => JSXElementChild::JSXElement(
                 Box::new(JSXElement {
-                    span: DUMMY_SP,
+                    span: e.span,
                     opening: e.opening.fold_with(self),
                     children: self.fold_jsx_element_childs(e.children),
                     closing: e.closing,
@@ -486,11 +1256,173 @@ impl Fold for TransformVisitor {
 
     fn fold_jsx_expr_container(&mut self, n: JSXExprContainer) -> JSXExprContainer {
         JSXExprContainer {
-            span: DUMMY_SP,
+            span: n.span,
             expr: (match n.expr {
                 JSXExpr::Expr(e) => JSXExpr::Expr(self.transform_expr_reactive(e)),
-                JSXExpr::JSXEmptyExpr(_) => JSXExpr::JSXEmptyExpr(JSXEmptyExpr { span: DUMMY_SP }),
+                JSXExpr::JSXEmptyExpr(empty) => JSXExpr::JSXEmptyExpr(empty),
             }),
         }
     }
 }
+
+// creates the branch `(v) => v` that renders the value of the _$if() test
+fn test_value() -> ExprOrSpread {
+    let value = Ident::new("v".into(), DUMMY_SP, Default::default());
+    Expr::Arrow(ArrowExpr {
+        span: DUMMY_SP,
+        params: vec![Pat::Ident(value.clone().into())],
+        body: Box::new(BlockStmtOrExpr::Expr(Box::new(Expr::Ident(value)))),
+        is_async: false,
+        is_generator: false,
+        type_params: Take::dummy(),
+        return_type: Take::dummy(),
+        ctxt: Default::default(),
+    })
+    .into()
+}
+
+// creates the argument `() => body`
+fn closure(body: Box<Expr>) -> ExprOrSpread {
+    Expr::Arrow(ArrowExpr {
+        span: body.span(),
+        params: Take::dummy(),
+        body: Box::new(BlockStmtOrExpr::Expr(body)),
+        is_async: false,
+        is_generator: false,
+        type_params: Take::dummy(),
+        return_type: Take::dummy(),
+        ctxt: Default::default(),
+    })
+    .into()
+}
+
+// reactive collection methods that accept a key extractor
+const KEYED_METHODS: &[&str] = &["map", "flatMap", "filter"];
+
+// creates the key extractor `(item) => item.id` for a callback that returns `<li key={item.id}>`,
+// warns if the returned element has no key
+fn key_extractor(callback: &ArrowExpr, method: &MemberProp) -> Option<ArrowExpr> {
+    let returned = match &*callback.body {
+        BlockStmtOrExpr::Expr(e) => Some(&**e),
+        BlockStmtOrExpr::BlockStmt(block) => match block.stmts.last() {
+            Some(Stmt::Return(ReturnStmt { arg: Some(arg), .. })) => Some(&**arg),
+            _ => None,
+        },
+    };
+    let element = match returned?.unwrap_parens() {
+        Expr::JSXElement(element) => element,
+        Expr::JSXFragment(fragment) => {
+            warn_missing_key(fragment.span, method);
+            return None;
+        }
+        _ => return None,
+    };
+    let key = element.opening.attrs.iter().find_map(|attr| match attr {
+        JSXAttrOrSpread::JSXAttr(JSXAttr { name: JSXAttrName::Ident(name), value, .. }) if &*name.sym == "key" => {
+            value.as_ref()
+        }
+        _ => None,
+    });
+    let key = match key {
+        Some(JSXAttrValue::JSXExprContainer(JSXExprContainer { expr: JSXExpr::Expr(e), .. })) => e.clone(),
+        Some(JSXAttrValue::Lit(lit)) => Box::new(Expr::Lit(lit.clone())),
+        _ => {
+            warn_missing_key(element.opening.span, method);
+            return None;
+        }
+    };
+    // the extractor only has the parameters of the callback, not the variables declared in its body
+    let mut params = Vec::new();
+    for param in &callback.params {
+        scope::pat_bindings(param, &mut params);
+    }
+    let outer = scope::free_variables(callback);
+    if let Some(local) = scope::free_variables(&*key)
+        .into_iter()
+        .find(|name| !params.contains(name) && !outer.contains(name))
+    {
+        errors::warning(
+            key.span(),
+            &format!("The key uses `{local}`, which is declared in the {}() callback, elements are recreated instead of reordered", method_name(method)),
+        );
+        return None;
+    }
+    Some(ArrowExpr {
+        span: key.span(),
+        params: callback.params.clone(),
+        body: Box::new(BlockStmtOrExpr::Expr(key)),
+        is_async: false,
+        is_generator: false,
+        type_params: None,
+        return_type: None,
+        ctxt: callback.ctxt,
+    })
+}
+
+fn warn_missing_key(span: Span, method: &MemberProp) {
+    errors::warning(
+        span,
+        &format!("Elements rendered by {}() should have a `key` attribute, so that they can be reordered instead of recreated", method_name(method)),
+    );
+}
+
+fn method_name(method: &MemberProp) -> &str {
+    match method {
+        MemberProp::Ident(i) => i.sym.as_str(),
+        _ => "map",
+    }
+}
+
+// `items` or `state.items`, values that can hold a reactive collection.
+// Array literals and the results of other calls are never reactive collections
+fn is_collection_receiver(expr: &Expr) -> bool {
+    matches!(expr, Expr::Ident(_) | Expr::Member(_))
+}
+
+// `x.$` or `x.$$`
+fn is_dollar_member(expr: &Expr) -> bool {
+    matches!(expr, Expr::Member(m) if m.prop.is_ident_with("$") || m.prop.is_ident_with("$$"))
+}
+
+fn imported_name(named: &ImportNamedSpecifier) -> &Atom {
+    named.imported.as_ref().map_or(&named.local.sym, export_name)
+}
+
+fn export_name(name: &ModuleExportName) -> &Atom {
+    match name {
+        ModuleExportName::Ident(i) => &i.sym,
+        ModuleExportName::Str(s) => &s.value,
+    }
+}
+
+// exports without a manifest entry might be reactive
+fn is_reactive_export(manifest: Option<&ReactivityManifest>, name: &str) -> bool {
+    manifest.is_none_or(|m| m.exports.get(name).copied().unwrap_or(true))
+}
+
+// namespace objects are reactive if any of their exports is
+fn has_reactive_exports(manifest: Option<&ReactivityManifest>) -> bool {
+    manifest.is_none_or(|m| m.exports.values().any(|reactive| *reactive))
+}
+
+// `import data from "./data.json" with { type: "json" }`
+fn is_json_import(import: &ImportDecl) -> bool {
+    !import.type_only
+        && import.with.as_ref().is_some_and(|with| {
+            with.props.iter().any(|prop| match prop {
+                PropOrSpread::Prop(prop) => match &**prop {
+                    Prop::KeyValue(kv) => {
+                        let key_is_type = match &kv.key {
+                            PropName::Ident(i) => i.sym == "type",
+                            PropName::Str(s) => s.value == "type",
+                            _ => false,
+                        };
+                        key_is_type
+                            && matches!(&*kv.value, Expr::Lit(Lit::Str(s)) if s.value == "json")
+                    }
+                    _ => false,
+                },
+                PropOrSpread::Spread(_) => false,
+            })
+        })
+}
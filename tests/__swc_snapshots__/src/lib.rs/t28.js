@@ -1,5 +1,5 @@
-const x = _$(()=>[
-        1,
-        2,
-        3
-    ]);
+const x = $$([
+    1,
+    2,
+    3
+]);
@@ -0,0 +1,8 @@
+const x = pointer(10);
+const y = always(()=>x * 2);
+const $ = jQuery;
+remote(()=>{
+    capture(x, y);
+    return x + y;
+});
+<div value={property(x, "name")}>{always(()=>x + 1)}{always(()=>$(x))}</div>;
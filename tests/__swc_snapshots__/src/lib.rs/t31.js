@@ -0,0 +1,4 @@
+import { $$ as pointer, prop as prop1, _$ } from "datex";
+const prop = "local";
+const x = pointer(10);
+<div value={prop1(x, "name")}>{_$(()=>x + 1)}</div>;
@@ -0,0 +1 @@
+const label = "static";
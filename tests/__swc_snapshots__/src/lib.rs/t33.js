@@ -0,0 +1,25 @@
+run((event, { detail = fallback })=>{
+    use(fallback, offset, list, factor, items, total, save, errorMessage, count, Component);
+    const local = event.value + offset;
+    let [first, ...rest] = list;
+    function helper(y) {
+        return y * factor;
+    }
+    for (const item of items){
+        total += item;
+    }
+    try {
+        save({
+            local,
+            first,
+            rest
+        });
+    } catch (error) {
+        console.error(error, errorMessage);
+    }
+    {
+        const offset = 1;
+        count = helper(offset);
+    }
+    return <Component value={local}>{detail}</Component>;
+});
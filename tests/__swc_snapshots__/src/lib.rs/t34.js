@@ -0,0 +1,18 @@
+import $ from "jquery";
+function Run(fn) {
+    return fn();
+}
+const a = $(x + 1);
+Run(()=>console.log(y));
+function list($, run) {
+    return [
+        $(x + 1),
+        run(()=>y)
+    ];
+}
+{
+    const run = (fn)=>fn();
+    run(()=>y);
+}try {} catch (run) {
+    run(()=>y);
+}
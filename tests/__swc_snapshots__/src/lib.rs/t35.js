@@ -0,0 +1,10 @@
+import { $ as reactive, run as transfer } from "datex-core-legacy/datex.ts";
+const a = _$(()=>x + 1);
+transfer(()=>{
+    use(y);
+    return y;
+});
+const b = _$(()=>x * 2);
+function inner(reactive) {
+    return reactive(x);
+}
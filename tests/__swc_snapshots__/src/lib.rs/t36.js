@@ -0,0 +1,26 @@
+const a = $$(1 + 2);
+const b = $$({
+    name: "x",
+    tags: [
+        "a",
+        "b"
+    ]
+});
+const c = _$(()=>({
+        get name () {
+            return x;
+        }
+    }));
+<div title={"a" + "b"} count={-1}>
+        {1 + 2}
+        {`static`}
+        {`a${1 + 1}`}
+        {[
+    1,
+    2
+]}
+        {true ? "yes" : "no"}
+        {_$tpl`value: ${x}`}
+        {_$(()=>x + 1)}
+        {_$if(()=>cond, ()=>1, ()=>2)}
+    </div>;
@@ -0,0 +1,18 @@
+import strings from "./strings.json" with {
+    type: "json"
+};
+import { format } from "./format.ts";
+const LABEL = "Total";
+export const SEPARATOR = LABEL + ": ";
+const COUNT = $$(0);
+let mutable = "x";
+const label = $$(LABEL + SEPARATOR);
+<div title={strings.title + SEPARATOR}>
+        {LABEL + SEPARATOR}
+        {_$tpl`${LABEL} (${COUNT})`}
+        {_$(()=>mutable + LABEL)}
+        {_$(()=>format + LABEL)}
+    </div>;
+function Item(LABEL) {
+    return <span>{_$(()=>LABEL + SEPARATOR)}</span>;
+}
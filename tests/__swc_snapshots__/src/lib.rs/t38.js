@@ -0,0 +1,20 @@
+import { user } from "./state.ts";
+const count = $$(0);
+const doubled = always(()=>count * 2);
+const alias = count;
+let plain = 5;
+function Counter({ step }, items) {
+    const local = $$(step);
+    const label = "Step " + step;
+    return <div title={label + "!"} user={prop(user, "name")} count={_$(()=>count + 1)}>
+            {step * 2}
+            {_$(()=>local + step)}
+            {_$(()=>doubled + plain)}
+            {prop(alias, "value")}
+            {items.map((item)=><li>{_$(()=>item.name + count)}</li>)}
+            {_$(()=>plain * 2)}
+        </div>;
+}
+function Shadowed(count) {
+    return <div>{count + 1}</div>;
+}
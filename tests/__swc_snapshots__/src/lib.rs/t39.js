@@ -0,0 +1,4 @@
+<div title={prop(prop(x, "user", true), "name", true)} label={prop(prop(x, "profile"), "label", true)}>
+        {prop(prop(x, "user", true), "name")}
+        {_$(()=>x?.user?.getName())}
+    </div>;
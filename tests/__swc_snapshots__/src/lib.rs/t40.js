@@ -0,0 +1,12 @@
+@template()
+class Counter extends Component {
+    #secret = $$(0);
+    count = $$(0);
+    render() {
+        return <div title={prop(this, "title")} data-secret={prop(_$(()=>this.#secret), "value")}>
+                {prop(this, "count")}
+                {_$(()=>this.#secret)}
+                {prop(prop(prop(this, "items"), 0), "label", true)}
+            </div>;
+    }
+}
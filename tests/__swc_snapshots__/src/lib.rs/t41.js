@@ -0,0 +1,6 @@
+const FIRST = 0;
+<div title={_$(()=>x[index])} label={prop(_$(()=>x[index]), "label")}>
+        {prop(x, FIRST)}
+        {prop(x, "first" + "Name")}
+        {_$(()=>x.items[x.selected])}
+    </div>;
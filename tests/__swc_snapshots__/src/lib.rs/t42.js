@@ -0,0 +1,5 @@
+const items = $$([]);
+const selected = $$(0);
+function Item({ index }) {
+    return <div title={prop(prop(items, index), "title")}>{_$(()=>items[selected])}</div>;
+}
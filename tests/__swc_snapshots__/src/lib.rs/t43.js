@@ -0,0 +1,7 @@
+import { _$, prop, _$if } from "uix";
+<div title={_$(()=>loggedIn ? "Logout" : "Login")}>
+        {_$if(()=>loggedIn, ()=><Profile user={prop(user, "name")}/>, (v)=>v)}
+        {_$if(()=>error, (v)=>v, ()=><span>No errors</span>)}
+        {_$if(()=>user.nickname, (v)=>v, ()=>prop(user, "name"), true)}
+        {_$if(()=>loading, ()=><Spinner/>, ()=>_$if(()=>items.length, ()=><List items={items}/>, ()=><>Empty</>))}
+    </div>;
@@ -0,0 +1,4 @@
+<div class={_$tpl`card ${prop(theme, "color")} ${_$(()=>active ? "active" : "")}`} style={_$tpl`width: ${width}px`}>
+        {_$tpl`Hello ${prop(user, "name")}, you have ${_$(()=>items.length + 1)} items`}
+        {`static ${1 + 1}`}
+    </div>;
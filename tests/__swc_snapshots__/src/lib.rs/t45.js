@@ -0,0 +1,7 @@
+<div title={_$(()=>tags.slice(0, 3).join(", "))} count={prop(todos.$.filter((t)=>t.done), "length")}>
+        {todos.$.filter((t)=>!t.done).$.sort((a, b)=>a.date - b.date).$.map((t)=><li>{prop(t, "title")}</li>)}
+        {todos.$.find((t)=>t.id == selected)}
+        {_$if(()=>todos.some((t)=>t.done), ()=><button>Clear</button>, (v)=>v)}
+        {todos.$.map((t)=><li>{prop(t, "title")}</li>)}
+        {prop(todos, "length")}
+    </div>;
@@ -0,0 +1,8 @@
+const todos = $$([]);
+function List({ items }) {
+    return <ul>
+            {todos.$.toSorted((a, b)=>a.date - b.date).$.map((t)=><li>{_$(()=>t.title + todos.length)}</li>)}
+            {items.map((i)=><li>{i}</li>)}
+            {_$(()=>todos.filter((t)=>t.done))}
+        </ul>;
+}
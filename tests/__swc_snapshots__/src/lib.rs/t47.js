@@ -0,0 +1,23 @@
+const tags = _$(()=>new Set([
+        "a",
+        "b"
+    ]));
+const users = new Map();
+const settings = {
+    keys: ()=>[]
+};
+<div>
+        {_$iter(form, "entries").$.map(([k, v])=><li>{k}: {prop(v, "label")}</li>)}
+        {_$iter(form, "keys")}
+        {_$iter(tags).$.map((tag)=><span>{prop(tag, "name")}</span>)}
+        {_$iter(tags)}
+        {prop(_$(()=>[
+        ...tags
+    ]), "length")}
+        {_$iter(users, "values").$.filter((u)=>u.active)}
+        {_$iter(tags, "entries")}
+        {_$(()=>settings.keys())}
+    </div>;
+function List({ Object }) {
+    return <ul>{_$(()=>Object.keys(form).map((k)=><li>{k}</li>))}</ul>;
+}
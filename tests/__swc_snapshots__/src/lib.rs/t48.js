@@ -0,0 +1,8 @@
+<div>
+        <ul>{todos.$.map((todo)=><li key={todo.id}>{prop(todo, "title")}</li>, (todo)=>todo.id)}</ul>
+        <ul>{_$iter(form, "entries").$.map(([name, value], i)=>{
+    const label = name + ":";
+    return <li key={name}>{label} {value}</li>;
+}, ([name, value], i)=>name)}</ul>
+        <ul>{todos.$.filter((t)=>t.done).$.map((t)=><li key="static">{prop(t, "title")}</li>, (t)=>"static")}</ul>
+    </div>;
@@ -0,0 +1 @@
+<form onsubmit={handler.bind(this)} onClick={handlers.save} oninput={makeHandler(id)} ref={setEl} stylesheet={theme.styles} value={prop(form, "name")} open={prop(state, "open")} online={prop(state, "online")} one={prop(x, "y")} onboarding={_$(()=>steps.length > 0)}/>;
@@ -0,0 +1,12 @@
+import { effect as onChange } from "uix";
+import { ref } from "vue";
+const a = always(()=>x + 1);
+const b = prop(form, "name");
+function Field({ val }) {
+    return <div title={always(()=>x + 1)} value={prop(form, "name")} state={_$(()=>val(x))}>
+            {always(()=>x + 1)}
+            {onChange(()=>console.log(x))}
+            {_$(()=>ref(x))}
+            {effect(()=>x)}
+        </div>;
+}
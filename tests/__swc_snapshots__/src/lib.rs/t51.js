@@ -0,0 +1,5 @@
+<p>
+        {_$if(()=>loggedIn, ()=>prop(user, "name"), ()=>"guest")}
+        {_$if(()=>open, ()=>items.$.map((i)=><li>{prop(i, "name")}</li>), ()=>null)}
+        {_$if(()=>count, (v)=>v, ()=>_$(()=>x + 1))}
+    </p>;
@@ -0,0 +1,11 @@
+function Tag({ label, name }) {
+    return <p title={_$(()=>label.slice(0, 3))} size={prop(label, "length")}>
+            {_$(()=>name.split(",").join(" "))}
+            {_$(()=>name.split(",").map((part)=><b>{_$(()=>part.trim())}</b>))}
+            {_$(()=>[
+            first,
+            second
+        ].filter((x)=>x.visible))}
+            {prop(items.$.filter((i)=>i.visible), "length")}
+        </p>;
+}
@@ -0,0 +1,12 @@
+<div>
+        <ul>{todos.$.map((t)=>{
+    const id = t.id;
+    return <li key={id}>{prop(t, "title")}</li>;
+})}</ul>
+        <ul>{todos.$.map((t)=><li key={prefix + t.id}>{prop(t, "title")}</li>, (t)=>prefix + t.id)}</ul>
+        <ul>{todos.$.map(function(t) {
+    return <li key={this.id}>{prop(t, "title")}</li>;
+}, ctx)}</ul>
+        <ul>{todos.$.map((t)=><li key={t.id}>{prop(t, "title")}</li>, ctx)}</ul>
+        {todos.$.find((t)=><li key={t.id}>{prop(t, "title")}</li>)}
+    </div>;
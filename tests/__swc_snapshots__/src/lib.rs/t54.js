@@ -0,0 +1,13 @@
+const location = "/chat";
+function Chat() {
+    const history = $$([]);
+    const prompt = "Hi";
+    const self = this;
+    const prop = (x)=>x;
+    return <button onclick:frontend={()=>{
+        use(history, prompt, self, prop, location);
+        history.push(prompt);
+        self.update(prop(location));
+        console.log(document.title);
+    }}/>;
+}
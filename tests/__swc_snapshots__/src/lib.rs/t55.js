@@ -0,0 +1,14 @@
+class Counter extends Component {
+    count = $$(0);
+    items = $$([]);
+    render() {
+        const format = function(n) {
+            return this.prefix + n;
+        };
+        return <div title={_$(()=>this.count + 1)} label={format(2)}>
+                {prop(prop(this, "items"), "length")}
+                {this.items.$.map((item)=><li>{item.name}</li>)}
+                {prop(this, "count")}
+            </div>;
+    }
+}
@@ -0,0 +1,12 @@
+const todos = $$([]);
+const names = new Array(3);
+function List({ label }) {
+    return <div title={_$(()=>todos.slice(0, 3).join(", "))} count={todos.$.length}>
+            {todos.$.slice(0, 3)}
+            {todos.$.includes(selected)}
+            {names.$.length}
+            {_$(()=>label.slice(0, 3))}
+            {prop(label, "length")}
+            {prop(todos.$.filter((t)=>t.done), "length")}
+        </div>;
+}
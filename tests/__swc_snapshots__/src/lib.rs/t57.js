@@ -0,0 +1,5 @@
+<div onmessage={h.msg} onmessageerror={h.error} ononline={h.online} online={prop(state, "online")}>
+        <video onprogress={h.progress} oncanplay={h.ready} onseeking={h.seek} onloadstart={h.start} onwaiting={h.wait}/>
+        <details onbeforetoggle={h.toggle}/>
+        <form onformdata={h.data}/>
+    </div>;
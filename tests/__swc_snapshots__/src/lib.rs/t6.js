@@ -1,4 +1,4 @@
 <button onclick:frontend={()=>{
-    use(console, x);
+    use(x);
     return console.log(x);
 }}/>;